@@ -1,6 +1,15 @@
+use crate::backend::BackendConfig;
+use crate::diff::{self, DiffLine};
 use crate::file_manager::FileManager;
+use crate::git::GitMode;
+use crate::history::RunRecord;
+use crate::project::ProjectConfig;
+use crate::retry::RetryPolicy;
+use crate::runner::{RunOptions, SuiteLimits};
+use crate::text_area::TextArea;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 fn default_true() -> bool {
     true
@@ -10,31 +19,198 @@ fn default_true() -> bool {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Verifier {
     pub name: String,
+    /// The verification criteria for an agent verifier, the shell command for a
+    /// command verifier, or the assertions for an assertion verifier.
     pub prompt: String,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    #[serde(default)]
+    pub kind: VerifierKind,
+    /// Paths or globs, relative to the working directory, whose changes this verifier
+    /// cares about. Used by the `affected` retry policy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// With `fail_fast` on, a failure skips the verifiers after this one, and they wait
+    /// for it to pass before starting.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gate: bool,
+}
+
+/// How a verifier decides pass/fail.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifierKind {
+    /// An agent judges the work against the prompt.
+    #[default]
+    Agent,
+    /// A shell command run in the working directory; exit 0 means pass.
+    Command,
+    /// File assertions checked without an agent, one per line (see `assertions`).
+    Assert,
+}
+
+impl VerifierKind {
+    /// The next kind, for cycling through them on the setup screen.
+    pub fn next(self) -> Self {
+        match self {
+            VerifierKind::Agent => VerifierKind::Command,
+            VerifierKind::Command => VerifierKind::Assert,
+            VerifierKind::Assert => VerifierKind::Agent,
+        }
+    }
+
+    /// Title of the setup field holding the verifier's body.
+    pub fn input_title(self) -> &'static str {
+        match self {
+            VerifierKind::Agent => "Verifier Prompt",
+            VerifierKind::Command => "Verifier Command (passes on exit 0)",
+            VerifierKind::Assert => "Verifier Assertion (exists <path>, json <path> [.key], matches|not_contains <path> <text>)",
+        }
+    }
+}
+
+/// Name of the suite stored in the top-level `verifiers` list.
+pub const DEFAULT_SUITE: &str = "default";
+
+/// Everything persisted in `verifiers.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub backend: BackendConfig,
+    #[serde(flatten)]
+    pub options: RunOptions,
+    /// The default suite.
+    #[serde(default)]
+    pub verifiers: Vec<Verifier>,
+    /// Named verifier suites besides the default one.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub suites: BTreeMap<String, Vec<Verifier>>,
+    /// Limits of individual suites, overriding the global ones, by suite name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub suite_limits: BTreeMap<String, SuiteLimits>,
+    /// The suite last picked on the setup screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suite: Option<String>,
+}
+
+impl Config {
+    /// Every suite name, the default suite first.
+    pub fn suite_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_SUITE.to_string())
+            .chain(self.suites.keys().cloned())
+            .collect()
+    }
+
+    pub fn suite(&self, name: &str) -> Option<&Vec<Verifier>> {
+        if name == DEFAULT_SUITE {
+            Some(&self.verifiers)
+        } else {
+            self.suites.get(name)
+        }
+    }
+
+    pub fn set_suite(&mut self, name: &str, verifiers: Vec<Verifier>) {
+        if name == DEFAULT_SUITE {
+            self.verifiers = verifiers;
+        } else {
+            self.suites.insert(name.to_string(), verifiers);
+        }
+    }
+}
+
+/// On-disk shape of `verifiers.json`. Older versions stored a bare verifier array.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredConfig {
+    Config(Box<Config>),
+    Legacy(Vec<Verifier>),
+}
+
+impl From<StoredConfig> for Config {
+    fn from(stored: StoredConfig) -> Self {
+        match stored {
+            StoredConfig::Config(config) => *config,
+            StoredConfig::Legacy(verifiers) => Config {
+                verifiers,
+                ..Config::default()
+            },
+        }
+    }
 }
 
 /// Status of each verifier during a run.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum VerifierStatus {
     Pending,
     Running,
     Passed,
     Failed,
+    TimedOut,
+    /// Not run this iteration because a gate verifier failed.
+    Skipped,
+}
+
+impl VerifierStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VerifierStatus::Pending => "pending",
+            VerifierStatus::Running => "running",
+            VerifierStatus::Passed => "passed",
+            VerifierStatus::Failed => "failed",
+            VerifierStatus::TimedOut => "timed out",
+            VerifierStatus::Skipped => "skipped",
+        }
+    }
 }
 
 /// Messages sent from the runner task to the TUI.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum RunnerMessage {
     Log(String),
     VerifierStatusUpdate {
+        // Serialized events carry the verifier's name instead (see `events::EventWriter`).
+        #[serde(skip_serializing)]
         index: usize,
         status: VerifierStatus,
     },
     IterationStart(u32),
     FileUpdated,
+    /// An agent call was killed after running longer than its timeout.
+    TimedOut {
+        agent: String,
+        seconds: u64,
+    },
+    /// An agent edited the file outside its allowed region; the edits were reverted.
+    Violation {
+        agent: String,
+        details: String,
+    },
+    /// Why a verifier failed this iteration: its feedback sections or the error it hit.
+    VerifierFeedback {
+        #[serde(skip_serializing)]
+        index: usize,
+        feedback: String,
+    },
+    /// A copy of the shared file after a step: `label` is "start", "worker" or the
+    /// verifier's name.
+    Snapshot {
+        label: String,
+        contents: String,
+    },
+    /// The run commits the worker's changes to this git branch.
+    GitBranch(String),
+    /// The run made a git worktree on `branch`, and the agents run in `dir` inside it.
+    Worktree {
+        branch: String,
+        dir: PathBuf,
+    },
     Done,
+    /// The run ended without every verifier passing, e.g. at the iteration limit.
+    Failed(String),
+    /// The run was stopped by an error it could not recover from.
+    Aborted(String),
     Error(String),
 }
 
@@ -44,15 +220,83 @@ pub enum Screen {
     Setup,
     Running,
     Done,
+    Failed,
+    Aborted,
+    /// Browsing past runs.
+    History,
+}
+
+impl Screen {
+    /// Whether the run has ended, one way or another.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Screen::Done | Screen::Failed | Screen::Aborted)
+    }
 }
 
 /// Which field is focused on the setup screen.
 #[derive(Clone, Debug, PartialEq)]
 pub enum SetupFocus {
     Prompt,
+    WorkingDir,
     VerifierName,
     VerifierPrompt,
     VerifierList,
+    Options,
+}
+
+/// Adjustable rows in the setup screen's options panel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetupOption {
+    MaxIterations,
+    TimeBudget,
+    Concurrency,
+    WorkerTimeout,
+    VerifierTimeout,
+    Git,
+    Retry,
+    FailFast,
+}
+
+impl SetupOption {
+    pub const ALL: [SetupOption; 8] = [
+        SetupOption::MaxIterations,
+        SetupOption::TimeBudget,
+        SetupOption::Concurrency,
+        SetupOption::WorkerTimeout,
+        SetupOption::VerifierTimeout,
+        SetupOption::Git,
+        SetupOption::Retry,
+        SetupOption::FailFast,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SetupOption::MaxIterations => "Max iterations",
+            SetupOption::TimeBudget => "Time budget",
+            SetupOption::Concurrency => "Parallel verifiers",
+            SetupOption::WorkerTimeout => "Worker timeout",
+            SetupOption::VerifierTimeout => "Verifier timeout",
+            SetupOption::Git => "Git commits",
+            SetupOption::Retry => "Retry verifiers",
+            SetupOption::FailFast => "Fail fast",
+        }
+    }
+}
+
+/// Timeout steps on the options panel.
+const TIMEOUT_STEP_SECS: i64 = 60;
+/// Unchanged lines shown around each change in the diff panel.
+const DIFF_CONTEXT: usize = 3;
+/// Time budget steps on the options panel.
+const BUDGET_STEP_SECS: i64 = 300;
+
+/// Format a timeout for display: "off" when disabled.
+fn timeout_label(secs: u64) -> String {
+    match secs {
+        0 => "off".to_string(),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
 }
 
 /// The full application state.
@@ -60,15 +304,41 @@ pub struct App {
     pub screen: Screen,
 
     // Setup state
-    pub prompt_input: String,
-    pub prompt_cursor: usize,
-    pub verifier_name_input: String,
-    pub verifier_name_cursor: usize,
-    pub verifier_prompt_input: String,
-    pub verifier_prompt_cursor: usize,
+    pub prompt_input: TextArea,
+    pub working_dir_input: TextArea,
+    pub verifier_name_input: TextArea,
+    pub verifier_prompt_input: TextArea,
+    pub verifier_kind_input: VerifierKind,
+    /// Index of the verifier loaded into the inputs for editing; Enter saves over it
+    /// instead of adding a new one.
+    pub editing_verifier: Option<usize>,
+    /// The verifiers of the active suite.
     pub verifiers: Vec<Verifier>,
+    /// Name of the active suite.
+    pub suite: String,
+    /// Every suite's verifiers as last loaded or switched away from. The active suite's
+    /// live list is `verifiers`.
+    pub suites: BTreeMap<String, Vec<Verifier>>,
+    /// Suites' own limits, from the config.
+    pub suite_limits: BTreeMap<String, SuiteLimits>,
+    /// The suite picker overlay, while it is open.
+    pub suite_picker: Option<SuitePicker>,
+    pub backend: BackendConfig,
+    /// The `.verifiers.toml` merged into the settings, if one was found.
+    pub project_config: Option<(PathBuf, ProjectConfig)>,
+    /// Why the project config could not be used, shown on the setup screen.
+    pub project_config_error: Option<String>,
+    /// The project config in full while the user is asked whether to trust the
+    /// commands it runs. `project_config` holds it without them until then.
+    pub trust_prompt: Option<ProjectConfig>,
+    /// The project config's commands are left out because it isn't trusted.
+    pub project_commands_ignored: bool,
+    /// Why the last `$EDITOR` session failed, shown on the setup screen.
+    pub editor_error: Option<String>,
+    pub run_options: RunOptions,
     pub setup_focus: SetupFocus,
     pub selected_verifier: usize,
+    pub selected_option: usize,
 
     // Prompt history
     pub prompt_history: Vec<String>,
@@ -77,6 +347,13 @@ pub struct App {
 
     // Running state
     pub verifier_statuses: Vec<(String, VerifierStatus)>,
+    /// Each verifier's most recent finished status (Pending if it never finished),
+    /// parallel to `verifier_statuses`. Unlike those, not reset between iterations.
+    pub last_statuses: Vec<VerifierStatus>,
+    /// Latest feedback per verifier, parallel to `verifier_statuses`.
+    pub verifier_feedback: Vec<Option<String>>,
+    /// Why the run failed or was aborted.
+    pub end_reason: Option<String>,
     pub logs: Vec<String>,
     pub file_contents: String,
     pub iteration: u32,
@@ -84,33 +361,100 @@ pub struct App {
     pub log_scroll: u16,
     pub file_scroll: u16,
     pub scroll_focus: ScrollFocus,
+    /// Copies of the shared file after each step of the run, oldest first.
+    pub snapshots: Vec<Snapshot>,
+    /// Whether the file panel shows the diff between two snapshots instead of the file.
+    pub show_diff: bool,
+    /// The snapshots being compared, as (from, to) indices. `None` follows the latest step.
+    pub diff_pair: Option<(usize, usize)>,
+    /// The diff between the compared snapshots, with the (from, to) pair it is for. Only
+    /// worked out while the diff panel is shown.
+    diff_cache: Option<((usize, usize), Vec<DiffLine>)>,
+    /// The run's git worktree, as (branch, agents' directory), until it is merged or
+    /// left for review.
+    pub worktree: Option<(String, PathBuf)>,
+    /// The record of the run in progress, until it is saved to the history.
+    pub current_run: Option<RunRecord>,
+
+    // History state
+    /// Past runs, newest first, as loaded when the History screen was opened.
+    pub runs: Vec<RunRecord>,
+    pub selected_run: usize,
+    /// Whether the History screen shows the selected run's final file or its logs.
+    pub history_view: ScrollFocus,
+    pub history_scroll: u16,
+    /// Why the selected run could not be resumed, shown on the History screen.
+    pub history_error: Option<String>,
 
     pub should_quit: bool,
 }
 
+/// The shared file as one step of a run left it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// 0 for the file the run started from.
+    pub iteration: u32,
+    /// "start", "worker" or a verifier's name.
+    pub label: String,
+    pub contents: String,
+}
+
+impl Snapshot {
+    pub fn title(&self) -> String {
+        if self.iteration == 0 {
+            self.label.clone()
+        } else {
+            format!("#{} {}", self.iteration, self.label)
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ScrollFocus {
     Log,
     File,
 }
 
+/// State of the suite picker overlay on the setup screen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuitePicker {
+    pub selected: usize,
+    /// Name typed for a new suite.
+    pub new_name: String,
+}
+
 impl App {
     pub fn new() -> Self {
         Self {
             screen: Screen::Setup,
-            prompt_input: String::new(),
-            prompt_cursor: 0,
-            verifier_name_input: String::new(),
-            verifier_name_cursor: 0,
-            verifier_prompt_input: String::new(),
-            verifier_prompt_cursor: 0,
+            prompt_input: TextArea::multi_line(),
+            working_dir_input: TextArea::single_line(),
+            verifier_name_input: TextArea::single_line(),
+            verifier_prompt_input: TextArea::multi_line(),
+            verifier_kind_input: VerifierKind::default(),
+            editing_verifier: None,
             verifiers: Vec::new(),
+            suite: DEFAULT_SUITE.to_string(),
+            suites: BTreeMap::new(),
+            suite_limits: BTreeMap::new(),
+            suite_picker: None,
+            backend: BackendConfig::default(),
+            project_config: None,
+            project_config_error: None,
+            trust_prompt: None,
+            project_commands_ignored: false,
+            editor_error: None,
+            run_options: RunOptions::default(),
             setup_focus: SetupFocus::Prompt,
             selected_verifier: 0,
+            selected_option: 0,
             prompt_history: Vec::new(),
             history_index: None,
             history_draft: String::new(),
             verifier_statuses: Vec::new(),
+            last_statuses: Vec::new(),
+            verifier_feedback: Vec::new(),
+            end_reason: None,
             logs: Vec::new(),
             file_contents: String::new(),
             iteration: 0,
@@ -118,64 +462,442 @@ impl App {
             log_scroll: 0,
             file_scroll: 0,
             scroll_focus: ScrollFocus::Log,
+            snapshots: Vec::new(),
+            show_diff: false,
+            diff_pair: None,
+            diff_cache: None,
+            worktree: None,
+            current_run: None,
+            runs: Vec::new(),
+            selected_run: 0,
+            history_view: ScrollFocus::File,
+            history_scroll: 0,
+            history_error: None,
             should_quit: false,
         }
     }
 
+    /// Add a verifier from the inputs, or save them over the one being edited.
     pub fn add_verifier(&mut self) {
-        let name = self.verifier_name_input.trim().to_string();
-        let prompt = self.verifier_prompt_input.trim().to_string();
-        if !name.is_empty() && !prompt.is_empty() {
-            self.verifiers.push(Verifier { name, prompt, enabled: true });
-            self.verifier_name_input.clear();
-            self.verifier_prompt_input.clear();
-            self.setup_focus = SetupFocus::VerifierName;
+        let name = self.verifier_name_input.text().trim().to_string();
+        let prompt = self.verifier_prompt_input.text().trim().to_string();
+        if name.is_empty() || prompt.is_empty() {
+            return;
+        }
+        match self.editing_verifier.take().and_then(|i| self.verifiers.get_mut(i).map(|v| (i, v))) {
+            Some((i, verifier)) => {
+                verifier.name = name;
+                verifier.prompt = prompt;
+                verifier.kind = self.verifier_kind_input;
+                self.selected_verifier = i;
+                self.setup_focus = SetupFocus::VerifierList;
+            }
+            None => {
+                self.verifiers.push(Verifier {
+                    name,
+                    prompt,
+                    enabled: true,
+                    kind: self.verifier_kind_input,
+                    files: Vec::new(),
+                    gate: false,
+                });
+                self.setup_focus = SetupFocus::VerifierName;
+            }
+        }
+        self.clear_verifier_inputs();
+    }
+
+    /// Load the selected verifier into the inputs so Enter saves over it.
+    pub fn edit_selected_verifier(&mut self) {
+        let Some(verifier) = self.verifiers.get(self.selected_verifier) else {
+            return;
+        };
+        self.verifier_name_input.set_text(verifier.name.clone());
+        self.verifier_prompt_input.set_text(verifier.prompt.clone());
+        self.verifier_kind_input = verifier.kind;
+        self.editing_verifier = Some(self.selected_verifier);
+        self.setup_focus = SetupFocus::VerifierPrompt;
+    }
+
+    /// The text Ctrl+G opens in `$EDITOR` for the focused field: the task prompt, the
+    /// verifier prompt being typed, or the selected verifier's prompt.
+    pub fn editable_text(&self) -> Option<&str> {
+        match self.setup_focus {
+            SetupFocus::Prompt => Some(self.prompt_input.text()),
+            SetupFocus::VerifierPrompt => Some(self.verifier_prompt_input.text()),
+            SetupFocus::VerifierList => self.verifiers.get(self.selected_verifier).map(|v| v.prompt.as_str()),
+            SetupFocus::WorkingDir | SetupFocus::VerifierName | SetupFocus::Options => None,
+        }
+    }
+
+    /// Put text back from `$EDITOR` into the field `editable_text` came from.
+    pub fn set_edited_text(&mut self, text: String) {
+        match self.setup_focus {
+            SetupFocus::Prompt => {
+                self.prompt_input.set_text(text);
+                self.history_index = None;
+            }
+            SetupFocus::VerifierPrompt => {
+                self.verifier_prompt_input.set_text(text);
+            }
+            SetupFocus::VerifierList => {
+                if let Some(verifier) = self.verifiers.get_mut(self.selected_verifier) {
+                    if !text.trim().is_empty() {
+                        verifier.prompt = text.trim().to_string();
+                    }
+                }
+            }
+            SetupFocus::WorkingDir | SetupFocus::VerifierName | SetupFocus::Options => {}
+        }
+    }
+
+    /// Stop editing without saving, leaving the verifier as it was.
+    pub fn cancel_edit(&mut self) {
+        if let Some(i) = self.editing_verifier.take() {
+            self.clear_verifier_inputs();
+            self.selected_verifier = i.min(self.verifiers.len().saturating_sub(1));
+            self.setup_focus = SetupFocus::VerifierList;
+        }
+    }
+
+    fn clear_verifier_inputs(&mut self) {
+        self.verifier_name_input.clear();
+        self.verifier_prompt_input.clear();
+    }
+
+    /// The text input with focus, if the focused field is one.
+    pub fn focused_input_mut(&mut self) -> Option<&mut TextArea> {
+        match self.setup_focus {
+            SetupFocus::Prompt => Some(&mut self.prompt_input),
+            SetupFocus::WorkingDir => Some(&mut self.working_dir_input),
+            SetupFocus::VerifierName => Some(&mut self.verifier_name_input),
+            SetupFocus::VerifierPrompt => Some(&mut self.verifier_prompt_input),
+            SetupFocus::VerifierList | SetupFocus::Options => None,
         }
     }
 
     pub fn remove_selected_verifier(&mut self) {
         if !self.verifiers.is_empty() {
             self.verifiers.remove(self.selected_verifier);
+            self.editing_verifier = match self.editing_verifier {
+                Some(i) if i == self.selected_verifier => None,
+                Some(i) if i > self.selected_verifier => Some(i - 1),
+                editing => editing,
+            };
             if self.selected_verifier >= self.verifiers.len() && self.selected_verifier > 0 {
                 self.selected_verifier -= 1;
             }
         }
     }
 
+    /// Move the selected verifier one place up (`-1`) or down (`1`). Verifiers run, and
+    /// are listed in the shared file, in this order.
+    pub fn move_selected_verifier(&mut self, delta: isize) {
+        let from = self.selected_verifier;
+        let Some(to) = from.checked_add_signed(delta).filter(|to| *to < self.verifiers.len()) else {
+            return;
+        };
+        self.verifiers.swap(from, to);
+        self.selected_verifier = to;
+        if self.editing_verifier == Some(from) {
+            self.editing_verifier = Some(to);
+        } else if self.editing_verifier == Some(to) {
+            self.editing_verifier = Some(from);
+        }
+    }
+
     pub fn toggle_selected_verifier(&mut self) {
         if let Some(v) = self.verifiers.get_mut(self.selected_verifier) {
             v.enabled = !v.enabled;
         }
     }
 
+    pub fn toggle_selected_gate(&mut self) {
+        if let Some(v) = self.verifiers.get_mut(self.selected_verifier) {
+            v.gate = !v.gate;
+        }
+    }
+
+    /// Current value of an options-panel row, for display.
+    pub fn option_value(&self, option: SetupOption) -> String {
+        match option {
+            SetupOption::MaxIterations => self.suite_limited(
+                self.suite_options().max_iterations_label(),
+                |limits| limits.max_iterations.is_some(),
+            ),
+            SetupOption::TimeBudget => self.suite_limited(
+                timeout_label(self.suite_options().time_budget_secs),
+                |limits| limits.time_budget_secs.is_some(),
+            ),
+            SetupOption::Concurrency => self.run_options.concurrency.to_string(),
+            SetupOption::WorkerTimeout => timeout_label(self.run_options.worker_timeout_secs),
+            SetupOption::VerifierTimeout => timeout_label(self.run_options.verifier_timeout_secs),
+            SetupOption::Git => self.run_options.git.label().to_string(),
+            SetupOption::Retry => self.run_options.retry.label().to_string(),
+            SetupOption::FailFast => if self.run_options.fail_fast { "on" } else { "off" }.to_string(),
+        }
+    }
+
+    /// The options a run of the active suite gets: the global ones with the suite's
+    /// limits applied.
+    pub fn suite_options(&self) -> RunOptions {
+        let mut options = self.run_options.clone();
+        if let Some(limits) = self.suite_limits.get(&self.suite) {
+            limits.apply(&mut options);
+        }
+        options
+    }
+
+    /// `value`, marked as the active suite's own when `set` says the suite sets it.
+    fn suite_limited(&self, value: String, set: impl Fn(&SuiteLimits) -> bool) -> String {
+        match self.suite_limits.get(&self.suite) {
+            Some(limits) if set(limits) => format!("{} ({})", value, self.suite),
+            _ => value,
+        }
+    }
+
+    /// Step the selected options-panel row up or down by `delta`.
+    pub fn adjust_selected_option(&mut self, delta: i32) {
+        let Some(option) = SetupOption::ALL.get(self.selected_option) else {
+            return;
+        };
+        match option {
+            // Stepping down stops at 0, which means unlimited. A limit the suite sets is
+            // changed for the suite.
+            SetupOption::MaxIterations => {
+                let limits = self.suite_limits.get_mut(&self.suite);
+                let max = match limits.and_then(|limits| limits.max_iterations.as_mut()) {
+                    Some(max) => max,
+                    None => &mut self.run_options.max_iterations,
+                };
+                *max = max.saturating_add_signed(delta);
+            }
+            SetupOption::TimeBudget => {
+                let limits = self.suite_limits.get_mut(&self.suite);
+                let secs = match limits.and_then(|limits| limits.time_budget_secs.as_mut()) {
+                    Some(secs) => secs,
+                    None => &mut self.run_options.time_budget_secs,
+                };
+                *secs = secs.saturating_add_signed(delta as i64 * BUDGET_STEP_SECS);
+            }
+            SetupOption::Concurrency => {
+                self.run_options.concurrency = self.run_options.concurrency.saturating_add_signed(delta as isize).max(1);
+            }
+            SetupOption::WorkerTimeout => {
+                let secs = &mut self.run_options.worker_timeout_secs;
+                *secs = secs.saturating_add_signed(delta as i64 * TIMEOUT_STEP_SECS);
+            }
+            SetupOption::VerifierTimeout => {
+                let secs = &mut self.run_options.verifier_timeout_secs;
+                *secs = secs.saturating_add_signed(delta as i64 * TIMEOUT_STEP_SECS);
+            }
+            SetupOption::Git => {
+                let modes = GitMode::ALL;
+                let current = modes.iter().position(|&mode| mode == self.run_options.git).unwrap_or(0);
+                let next = (current as isize + delta as isize).rem_euclid(modes.len() as isize);
+                self.run_options.git = modes[next as usize];
+            }
+            SetupOption::Retry => {
+                let policies = RetryPolicy::ALL;
+                let current = policies.iter().position(|&p| p == self.run_options.retry).unwrap_or(0);
+                let next = (current as isize + delta as isize).rem_euclid(policies.len() as isize);
+                self.run_options.retry = policies[next as usize];
+            }
+            SetupOption::FailFast => self.run_options.fail_fast = !self.run_options.fail_fast,
+        }
+    }
+
+    /// Fill in the working directory field, e.g. from `--cwd` or the launch directory.
+    pub fn set_working_dir(&mut self, dir: &Path) {
+        self.working_dir_input.set_text(dir.display().to_string());
+    }
+
+    /// The working directory field as an absolute path, if it names an existing directory.
+    /// A leading `~` stands for the home directory.
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        let input = self.working_dir_input.text().trim();
+        let path = match input.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                dirs::home_dir()?.join(rest.trim_start_matches('/'))
+            }
+            _ => PathBuf::from(input),
+        };
+        path.canonicalize().ok().filter(|p| p.is_dir())
+    }
+
+    /// Load every suite from `config` and make `suite` the active one. A suite that
+    /// doesn't exist yet starts out empty.
+    pub fn load_suites(&mut self, config: &Config, suite: &str) {
+        self.suites = config
+            .suite_names()
+            .into_iter()
+            .map(|name| {
+                let verifiers = config.suite(&name).cloned().unwrap_or_default();
+                (name, verifiers)
+            })
+            .collect();
+        self.suite_limits = config.suite_limits.clone();
+        self.suite = suite.to_string();
+        self.verifiers = self.suites.get(suite).cloned().unwrap_or_default();
+        self.selected_verifier = 0;
+    }
+
+    /// Names of all suites, the default suite first.
+    pub fn suite_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.suites.keys().filter(|n| *n != DEFAULT_SUITE).cloned().collect();
+        names.insert(0, DEFAULT_SUITE.to_string());
+        names
+    }
+
+    /// Whether `suite` comes from the project's `.verifiers.toml`.
+    pub fn is_project_suite(&self, suite: &str) -> bool {
+        self.project_config
+            .as_ref()
+            .is_some_and(|(_, project)| project.suite(suite).is_some())
+    }
+
+    pub fn open_suite_picker(&mut self) {
+        let selected = self.suite_names().iter().position(|n| *n == self.suite).unwrap_or(0);
+        self.suite_picker = Some(SuitePicker {
+            selected,
+            new_name: String::new(),
+        });
+    }
+
+    /// Make `suite` active, keeping the current suite's edits in `suites`. A new name
+    /// starts as a copy of the current verifiers.
+    pub fn switch_suite(&mut self, suite: &str) {
+        self.suites.insert(self.suite.clone(), self.verifiers.clone());
+        let verifiers = self
+            .suites
+            .entry(suite.to_string())
+            .or_insert_with(|| self.verifiers.clone())
+            .clone();
+        self.suite = suite.to_string();
+        self.verifiers = verifiers;
+        self.selected_verifier = 0;
+        self.editing_verifier = None;
+        self.suite_picker = None;
+    }
+
+    /// Forget a suite, switching to the default suite if it was active. The default
+    /// suite and project suites are kept; returns whether it was removed.
+    pub fn remove_suite(&mut self, suite: &str) -> bool {
+        if suite == DEFAULT_SUITE || self.is_project_suite(suite) || self.suites.remove(suite).is_none() {
+            return false;
+        }
+        if self.suite == suite {
+            self.suite = DEFAULT_SUITE.to_string();
+            self.verifiers = self.suites.get(DEFAULT_SUITE).cloned().unwrap_or_default();
+            self.selected_verifier = 0;
+            self.editing_verifier = None;
+        }
+        true
+    }
+
+    /// Use the project config the trust prompt is asking about in full, commands
+    /// included, merged over `config`. Returns its path and contents, to remember it as
+    /// trusted.
+    pub fn trust_project(&mut self, mut config: Config) -> Option<(PathBuf, ProjectConfig)> {
+        let project = self.trust_prompt.take()?;
+        let (path, _) = self.project_config.take()?;
+        project.apply(&mut config);
+        let suite = self.suite.clone();
+        self.load_suites(&config, &suite);
+        self.backend = config.backend;
+        self.run_options = config.options;
+        self.project_config = Some((path.clone(), project.clone()));
+        self.project_commands_ignored = false;
+        Some((path, project))
+    }
+
     pub fn can_start(&self) -> bool {
-        !self.prompt_input.trim().is_empty() && self.verifiers.iter().any(|v| v.enabled)
+        !self.prompt_input.text().trim().is_empty()
+            && self.verifiers.iter().any(|v| v.enabled)
+            && self.working_dir().is_some()
     }
 
-    pub fn start_running(&mut self, file_manager: FileManager) {
+    /// Switch to the Running screen for a run of `verifiers` (the enabled ones, in order).
+    pub fn start_running(&mut self, file_manager: FileManager, verifiers: &[Verifier]) {
         self.screen = Screen::Running;
         self.file_manager = Some(file_manager);
-        self.verifier_statuses = self
-            .verifiers
+        self.verifier_statuses = verifiers
             .iter()
-            .filter(|v| v.enabled)
             .map(|v| (v.name.clone(), VerifierStatus::Pending))
             .collect();
+        self.last_statuses = vec![VerifierStatus::Pending; self.verifier_statuses.len()];
+        self.verifier_feedback = vec![None; self.verifier_statuses.len()];
+        self.snapshots.clear();
+        self.diff_pair = None;
+        self.diff_cache = None;
+    }
+
+    /// The snapshots the diff panel compares, as (from, to) indices.
+    pub fn diff_pair(&self) -> Option<(usize, usize)> {
+        let last = self.snapshots.len().checked_sub(1)?;
+        Some(self.diff_pair.unwrap_or((last.saturating_sub(1), last)))
+    }
+
+    /// Show or hide the diff panel in place of the file.
+    pub fn toggle_diff(&mut self) {
+        self.show_diff = !self.show_diff;
+        self.file_scroll = 0;
+        self.refresh_diff();
+    }
+
+    /// Move the compared snapshots: `step` moves both through the run, keeping the gap
+    /// between them; `from_delta` moves only the one compared from, up to the other.
+    pub fn move_diff(&mut self, step: isize, from_delta: isize) {
+        let Some((from, to)) = self.diff_pair() else {
+            return;
+        };
+        let gap = to - from;
+        let to = to.saturating_add_signed(step).min(self.snapshots.len() - 1);
+        let from = to.saturating_sub(gap).saturating_add_signed(from_delta).min(to);
+        self.diff_pair = Some((from, to));
+        self.file_scroll = 0;
+        self.refresh_diff();
+    }
+
+    /// The diff between the compared snapshots, with unchanged stretches collapsed.
+    pub fn diff_lines(&self) -> &[DiffLine] {
+        self.diff_cache.as_ref().map_or(&[], |(_, lines)| lines)
+    }
+
+    /// Diff the compared snapshots again if the panel is shown and they changed since
+    /// the last time.
+    fn refresh_diff(&mut self) {
+        if !self.show_diff {
+            return;
+        }
+        let Some((from, to)) = self.diff_pair() else {
+            self.diff_cache = None;
+            return;
+        };
+        if self.diff_cache.as_ref().is_some_and(|(pair, _)| *pair == (from, to)) {
+            return;
+        }
+        let lines = diff::diff_lines(&self.snapshots[from].contents, &self.snapshots[to].contents);
+        self.diff_cache = Some(((from, to), diff::with_context(lines, DIFF_CONTEXT)));
     }
 
     pub fn edit_and_rerun(&mut self) {
         self.screen = Screen::Setup;
         self.setup_focus = SetupFocus::Prompt;
-        self.prompt_cursor = self.prompt_input.len();
-        self.verifier_name_input.clear();
-        self.verifier_name_cursor = 0;
-        self.verifier_prompt_input.clear();
-        self.verifier_prompt_cursor = 0;
+        self.prompt_input.move_to_end();
+        self.clear_verifier_inputs();
+        self.editing_verifier = None;
         self.history_index = None;
         self.history_draft.clear();
         self.verifier_statuses.clear();
+        self.last_statuses.clear();
+        self.verifier_feedback.clear();
+        self.end_reason = None;
         self.logs.clear();
         self.file_contents.clear();
+        self.snapshots.clear();
+        self.diff_pair = None;
+        self.diff_cache = None;
         self.iteration = 0;
         self.file_manager = None;
         self.log_scroll = 0;
@@ -186,17 +908,20 @@ impl App {
     pub fn reset_for_new_run(&mut self) {
         self.screen = Screen::Setup;
         self.prompt_input.clear();
-        self.prompt_cursor = 0;
-        self.verifier_name_input.clear();
-        self.verifier_name_cursor = 0;
-        self.verifier_prompt_input.clear();
-        self.verifier_prompt_cursor = 0;
+        self.clear_verifier_inputs();
+        self.editing_verifier = None;
         self.setup_focus = SetupFocus::Prompt;
         self.history_index = None;
         self.history_draft.clear();
         self.verifier_statuses.clear();
+        self.last_statuses.clear();
+        self.verifier_feedback.clear();
+        self.end_reason = None;
         self.logs.clear();
         self.file_contents.clear();
+        self.snapshots.clear();
+        self.diff_pair = None;
+        self.diff_cache = None;
         self.iteration = 0;
         self.file_manager = None;
         self.log_scroll = 0;
@@ -204,7 +929,112 @@ impl App {
         self.scroll_focus = ScrollFocus::Log;
     }
 
-    pub fn handle_runner_message(&mut self, msg: RunnerMessage) {
+    /// Start recording the run about to begin, with the verifiers it will run.
+    pub fn start_record(&mut self, verifiers: Vec<Verifier>) -> &RunRecord {
+        let file_path = self.file_manager.as_ref().map(|fm| fm.path.clone()).unwrap_or_default();
+        self.current_run.insert(RunRecord::new(
+            self.prompt_input.text(),
+            &self.suite,
+            verifiers,
+            &self.backend,
+            &self.suite_options(),
+            &file_path,
+        ))
+    }
+
+    /// Close the current run's record with its final file and logs, ready to save.
+    pub fn finish_record(&mut self) -> Option<RunRecord> {
+        let mut record = self.current_run.take()?;
+        let final_file = self
+            .file_manager
+            .as_ref()
+            .and_then(|fm| fm.read_contents().ok())
+            .unwrap_or_else(|| self.file_contents.clone());
+        record.finish(final_file, self.logs.clone());
+        Some(record)
+    }
+
+    pub fn open_history(&mut self, runs: Vec<RunRecord>) {
+        self.screen = Screen::History;
+        self.runs = runs;
+        self.selected_run = 0;
+        self.history_scroll = 0;
+        self.history_error = None;
+    }
+
+    pub fn selected_run(&self) -> Option<&RunRecord> {
+        self.runs.get(self.selected_run)
+    }
+
+    /// Back to the setup screen with the selected run's prompt, working directory,
+    /// options and verifiers, ready to start again. The verifiers replace those of the
+    /// run's suite.
+    pub fn relaunch_selected_run(&mut self) {
+        let Some(run) = self.runs.get(self.selected_run).cloned() else {
+            return;
+        };
+        self.prompt_input.set_text(run.prompt);
+        self.set_working_dir(&run.working_dir);
+        self.switch_suite(&run.suite);
+        self.merge_run_verifiers(run.verifiers);
+        let global = self.run_options.clone();
+        self.run_options = RunOptions {
+            working_dir: run.working_dir.clone(),
+            ..run.options
+        };
+        if let Some(limits) = self.suite_limits.get(&self.suite) {
+            limits.unapply(&mut self.run_options, &global);
+        }
+        self.history_index = None;
+        self.setup_focus = SetupFocus::Prompt;
+        self.screen = Screen::Setup;
+    }
+
+    /// Set up the active suite's verifiers as a run used them. The run only recorded the
+    /// ones it ran: a suite verifier of the same name takes its settings, the suite's
+    /// others are kept but disabled, and any the suite no longer has are added back.
+    fn merge_run_verifiers(&mut self, run_verifiers: Vec<Verifier>) {
+        for verifier in &mut self.verifiers {
+            verifier.enabled = false;
+        }
+        for run_verifier in run_verifiers {
+            match self.verifiers.iter_mut().find(|v| v.name == run_verifier.name) {
+                Some(verifier) => *verifier = run_verifier,
+                None => self.verifiers.push(run_verifier),
+            }
+        }
+    }
+
+    /// Continue the selected run where it stopped: its setup is loaded as if relaunched,
+    /// its logs restored, and the Running screen opened. Returns what to start
+    /// `run_loop` with, or `None` (with `history_error` set) if it can't be resumed.
+    pub fn resume_selected_run(&mut self) -> Option<(FileManager, Vec<Verifier>, RunOptions)> {
+        let mut run = self.runs.get(self.selected_run)?.clone();
+        let (file_manager, options) = match run.resume() {
+            Ok(resumed) => resumed,
+            Err(e) => {
+                self.history_error = Some(e);
+                return None;
+            }
+        };
+        self.relaunch_selected_run();
+        self.start_running(file_manager.clone(), &run.verifiers);
+        self.file_contents = file_manager.read_contents().unwrap_or_default();
+        self.logs = run.logs.clone();
+        self.logs.push(format!(
+            "Resuming run {} at iteration {}",
+            run.id,
+            options.resume.as_ref().map_or(1, |r| r.iteration)
+        ));
+        let verifiers = run.verifiers.clone();
+        self.current_run = Some(run);
+        Some((file_manager, verifiers, options))
+    }
+
+    /// Apply a runner message to the screen and the run's record. Returns whether the
+    /// record changed, so it needs saving again.
+    pub fn handle_runner_message(&mut self, msg: RunnerMessage) -> bool {
+        let changed = self.current_run.as_mut().is_some_and(|record| record.observe(&msg));
         match msg {
             RunnerMessage::Log(text) => {
                 self.logs.push(text);
@@ -215,6 +1045,20 @@ impl App {
                 }
             }
             RunnerMessage::VerifierStatusUpdate { index, status } => {
+                match status {
+                    // A skipped verifier's last verdict still stands.
+                    VerifierStatus::Pending | VerifierStatus::Skipped => {}
+                    VerifierStatus::Running => {
+                        if let Some(feedback) = self.verifier_feedback.get_mut(index) {
+                            *feedback = None;
+                        }
+                    }
+                    _ => {
+                        if let Some(last) = self.last_statuses.get_mut(index) {
+                            *last = status.clone();
+                        }
+                    }
+                }
                 if let Some(vs) = self.verifier_statuses.get_mut(index) {
                     vs.1 = status;
                 }
@@ -233,14 +1077,49 @@ impl App {
                     }
                 }
             }
+            RunnerMessage::TimedOut { agent, seconds } => {
+                self.logs.push(format!("TIMEOUT: {} killed after {}s", agent, seconds));
+            }
+            RunnerMessage::Violation { agent, details } => {
+                self.logs.push(format!("VIOLATION by {}: {} (reverted)", agent, details));
+            }
+            RunnerMessage::VerifierFeedback { index, feedback } => {
+                if let Some(slot) = self.verifier_feedback.get_mut(index) {
+                    *slot = Some(feedback);
+                }
+            }
             RunnerMessage::Done => {
                 self.screen = Screen::Done;
                 self.logs.push("All verifiers passed!".to_string());
             }
+            RunnerMessage::Failed(reason) => {
+                self.screen = Screen::Failed;
+                self.logs.push(format!("FAILED: {}", reason));
+                self.end_reason = Some(reason);
+            }
+            RunnerMessage::Aborted(reason) => {
+                self.screen = Screen::Aborted;
+                self.logs.push(format!("ABORTED: {}", reason));
+                self.end_reason = Some(reason);
+            }
+            // Already logged by the runner; the history record keeps it for resuming.
+            RunnerMessage::GitBranch(_) => {}
+            RunnerMessage::Worktree { branch, dir } => {
+                self.worktree = Some((branch, dir));
+            }
+            RunnerMessage::Snapshot { label, contents } => {
+                self.snapshots.push(Snapshot {
+                    iteration: self.iteration,
+                    label,
+                    contents,
+                });
+                self.refresh_diff();
+            }
             RunnerMessage::Error(e) => {
                 self.logs.push(format!("ERROR: {}", e));
             }
         }
+        changed
     }
 }
 
@@ -251,22 +1130,79 @@ fn verifiers_path() -> PathBuf {
     config_dir.join("verifiers.json")
 }
 
-pub fn save_verifiers(verifiers: &[Verifier]) {
+/// Parse a config file, accepting both the current and the legacy array format.
+pub fn parse_config(contents: &str) -> serde_json::Result<Config> {
+    serde_json::from_str::<StoredConfig>(contents).map(Config::from)
+}
+
+pub fn load_config() -> Config {
+    let path = verifiers_path();
+    std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| parse_config(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(config: &Config) {
     let path = verifiers_path();
     if let Some(parent) = path.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
-    if let Ok(json) = serde_json::to_string_pretty(verifiers) {
+    if let Ok(json) = serde_json::to_string_pretty(config) {
         let _ = std::fs::write(&path, json);
     }
 }
 
-pub fn load_verifiers() -> Vec<Verifier> {
-    let path = verifiers_path();
-    std::fs::read_to_string(&path)
-        .ok()
-        .and_then(|contents| serde_json::from_str(&contents).ok())
-        .unwrap_or_default()
+/// The global config with the project config, if any, merged over it.
+pub fn load_merged_config(project: Option<&ProjectConfig>) -> Config {
+    let mut config = load_config();
+    if let Some(project) = project {
+        project.apply(&mut config);
+    }
+    config
+}
+
+/// Save the setup screen's run options and remember `suite` as the last one used,
+/// keeping the rest of the stored config intact. Settings that came from a project's
+/// `.verifiers.toml` keep their global values.
+pub fn save_setup(
+    suite: &str,
+    options: &RunOptions,
+    suite_limits: &BTreeMap<String, SuiteLimits>,
+    project: Option<&ProjectConfig>,
+) {
+    let global = load_config();
+    let mut config = global.clone();
+    config.options = options.clone();
+    config.suite_limits = suite_limits.clone();
+    config.suite = Some(suite.to_string());
+    if let Some(project) = project {
+        project.unapply(&mut config, &global);
+    }
+    save_config(&config);
+}
+
+/// Save one suite's verifiers, keeping the rest of the stored config intact. Suites
+/// defined by the project config are not written to the global file.
+pub fn save_verifiers(suite: &str, verifiers: &[Verifier], project: Option<&ProjectConfig>) {
+    if project.is_some_and(|project| project.suite(suite).is_some()) {
+        return;
+    }
+    let mut config = load_config();
+    config.set_suite(suite, verifiers.to_vec());
+    save_config(&config);
+}
+
+/// Delete a named suite from the global config. The default suite and suites defined
+/// by the project config can't be deleted; returns whether it was.
+pub fn delete_suite(suite: &str, project: Option<&ProjectConfig>) -> bool {
+    if suite == DEFAULT_SUITE || project.is_some_and(|project| project.suite(suite).is_some()) {
+        return false;
+    }
+    let mut config = load_config();
+    let deleted = config.suites.remove(suite).is_some();
+    save_config(&config);
+    deleted
 }
 
 const MAX_PROMPT_HISTORY: usize = 50;
@@ -311,3 +1247,339 @@ pub fn add_to_prompt_history(history: &mut Vec<String>, prompt: &str) {
         history.drain(..excess);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verifier(name: &str) -> Verifier {
+        Verifier {
+            name: name.to_string(),
+            prompt: "p".to_string(),
+            enabled: true,
+            kind: VerifierKind::Agent,
+            files: Vec::new(),
+            gate: false,
+        }
+    }
+
+    #[test]
+    fn adjusting_max_iterations_reaches_unlimited() {
+        let mut app = App::new();
+        app.run_options.max_iterations = 1;
+        app.selected_option = 0;
+        app.adjust_selected_option(-1);
+        assert_eq!(app.option_value(SetupOption::MaxIterations), "unlimited");
+        app.adjust_selected_option(-1);
+        assert_eq!(app.run_options.max_iterations, 0);
+        app.adjust_selected_option(1);
+        assert_eq!(app.option_value(SetupOption::MaxIterations), "1");
+
+        app.selected_option = 2;
+        app.run_options.concurrency = 1;
+        app.adjust_selected_option(-1);
+        assert_eq!(app.run_options.concurrency, 1);
+
+        // The git mode wraps around.
+        app.selected_option = 5;
+        app.adjust_selected_option(1);
+        assert_eq!(app.option_value(SetupOption::Git), "branch");
+        app.adjust_selected_option(1);
+        assert_eq!(app.option_value(SetupOption::Git), "worktree");
+        app.adjust_selected_option(1);
+        assert_eq!(app.run_options.git, GitMode::Off);
+        app.adjust_selected_option(-1);
+        assert_eq!(app.run_options.git, GitMode::Worktree);
+
+        app.selected_option = 6;
+        app.adjust_selected_option(-1);
+        assert_eq!(app.run_options.retry, RetryPolicy::Affected);
+        app.selected_option = 7;
+        app.adjust_selected_option(1);
+        assert!(app.run_options.fail_fast);
+    }
+
+    #[test]
+    fn timeouts_step_by_minutes_and_turn_off_at_zero() {
+        let mut app = App::new();
+        app.selected_option = 4;
+        assert_eq!(app.option_value(SetupOption::VerifierTimeout), "off");
+        app.adjust_selected_option(1);
+        app.adjust_selected_option(1);
+        assert_eq!(app.option_value(SetupOption::VerifierTimeout), "2m");
+        app.run_options.verifier_timeout_secs = 90;
+        assert_eq!(app.option_value(SetupOption::VerifierTimeout), "90s");
+        app.adjust_selected_option(-1);
+        app.adjust_selected_option(-1);
+        assert_eq!(app.run_options.verifier_timeout_secs, 0);
+    }
+
+    #[test]
+    fn suite_limits_override_the_global_ones() {
+        let mut app = App::new();
+        app.run_options.max_iterations = 10;
+        app.suite_limits.insert(
+            "smoke".to_string(),
+            SuiteLimits {
+                max_iterations: Some(2),
+                time_budget_secs: None,
+            },
+        );
+        app.switch_suite("smoke");
+        assert_eq!(app.option_value(SetupOption::MaxIterations), "2 (smoke)");
+        assert_eq!(app.suite_options().max_iterations, 2);
+
+        // The suite's limit is the one adjusted; the budget it doesn't set is global.
+        app.selected_option = 0;
+        app.adjust_selected_option(1);
+        assert_eq!(app.suite_limits["smoke"].max_iterations, Some(3));
+        assert_eq!(app.run_options.max_iterations, 10);
+        app.selected_option = 1;
+        app.adjust_selected_option(1);
+        assert_eq!(app.option_value(SetupOption::TimeBudget), "5m");
+        assert_eq!(app.run_options.time_budget_secs, 300);
+
+        app.switch_suite(DEFAULT_SUITE);
+        assert_eq!(app.option_value(SetupOption::MaxIterations), "10");
+    }
+
+    #[test]
+    fn diff_follows_the_latest_step_until_moved() {
+        let mut app = App::new();
+        assert_eq!(app.diff_pair(), None);
+        app.handle_runner_message(RunnerMessage::Snapshot {
+            label: "start".to_string(),
+            contents: "start\n".to_string(),
+        });
+        // Nothing is diffed while the panel is hidden.
+        assert!(app.diff_lines().is_empty());
+        app.snapshots.clear();
+        app.toggle_diff();
+        for (i, label) in ["start", "worker", "lint", "tests"].into_iter().enumerate() {
+            if i == 1 {
+                app.handle_runner_message(RunnerMessage::IterationStart(1));
+            }
+            app.handle_runner_message(RunnerMessage::Snapshot {
+                label: label.to_string(),
+                contents: format!("{}\n", label),
+            });
+            assert_eq!(app.diff_pair(), Some((i.saturating_sub(1), i)));
+        }
+        assert_eq!(
+            app.diff_lines(),
+            [DiffLine::Removed("lint".to_string()), DiffLine::Added("tests".to_string())]
+        );
+        assert_eq!(app.snapshots[0].title(), "start");
+        assert_eq!(app.snapshots[2].title(), "#1 lint");
+
+        app.move_diff(-1, 0);
+        assert_eq!(app.diff_pair(), Some((1, 2)));
+        app.move_diff(0, -1);
+        assert_eq!(app.diff_pair(), Some((0, 2)));
+        assert_eq!(app.diff_lines()[0], DiffLine::Removed("start".to_string()));
+        // Stepping keeps the gap; the "from" side stops at the first snapshot.
+        app.move_diff(1, 0);
+        assert_eq!(app.diff_pair(), Some((1, 3)));
+        app.move_diff(-5, 0);
+        assert_eq!(app.diff_pair(), Some((0, 0)));
+        app.move_diff(0, 1);
+        assert_eq!(app.diff_pair(), Some((0, 0)));
+    }
+
+    #[test]
+    fn failed_run_keeps_last_failures_and_feedback() {
+        let mut app = App::new();
+        app.verifiers = vec![verifier("a"), verifier("b")];
+        let verifiers = app.verifiers.clone();
+        app.start_running(FileManager::create(&[], "").unwrap(), &verifiers);
+        let status = |index, status| RunnerMessage::VerifierStatusUpdate { index, status };
+        for msg in [
+            RunnerMessage::IterationStart(1),
+            status(0, VerifierStatus::Running),
+            status(0, VerifierStatus::Passed),
+            status(1, VerifierStatus::Running),
+            RunnerMessage::VerifierFeedback { index: 1, feedback: "=== b ===\nno docs\n".to_string() },
+            status(1, VerifierStatus::Failed),
+            RunnerMessage::IterationStart(2),
+            RunnerMessage::Aborted("Worker failed: boom".to_string()),
+        ] {
+            app.handle_runner_message(msg);
+        }
+
+        assert_eq!(app.screen, Screen::Aborted);
+        assert!(app.screen.is_finished());
+        assert_eq!(app.end_reason.as_deref(), Some("Worker failed: boom"));
+        assert_eq!(app.last_statuses, vec![VerifierStatus::Passed, VerifierStatus::Failed]);
+        assert_eq!(app.verifier_feedback[1].as_deref(), Some("=== b ===\nno docs\n"));
+
+        let _ = std::fs::remove_file(&app.file_manager.take().unwrap().path);
+        app.edit_and_rerun();
+        assert_eq!(app.screen, Screen::Setup);
+        assert!(app.end_reason.is_none() && app.verifier_feedback.is_empty());
+    }
+
+    #[test]
+    fn switching_suites_keeps_unsaved_edits() {
+        let mut config = Config {
+            verifiers: vec![verifier("lint")],
+            ..Default::default()
+        };
+        config.suites.insert("backend".to_string(), vec![verifier("tests")]);
+        let mut app = App::new();
+        app.load_suites(&config, "backend");
+        assert_eq!(app.suite_names(), vec!["default", "backend"]);
+        assert_eq!(app.verifiers[0].name, "tests");
+
+        app.verifiers.push(verifier("bench"));
+        app.switch_suite(DEFAULT_SUITE);
+        assert_eq!(app.verifiers.len(), 1);
+        app.switch_suite("backend");
+        assert_eq!(app.verifiers.len(), 2);
+
+        // A new suite starts from the current verifiers.
+        app.switch_suite("perf");
+        assert_eq!(app.verifiers.len(), 2);
+        assert_eq!(app.suite_names(), vec!["default", "backend", "perf"]);
+
+        assert!(!app.remove_suite(DEFAULT_SUITE));
+        assert!(app.remove_suite("perf"));
+        assert_eq!(app.suite, DEFAULT_SUITE);
+        assert_eq!(app.verifiers[0].name, "lint");
+    }
+
+    #[test]
+    fn verifiers_are_edited_in_place_and_reordered() {
+        let mut app = App::new();
+        for (name, prompt) in [("lint", "clippy"), ("tests", "cargo test")] {
+            app.verifier_name_input.set_text(name);
+            app.verifier_prompt_input.set_text(prompt);
+            app.add_verifier();
+        }
+        app.verifiers[1].enabled = false;
+
+        app.selected_verifier = 1;
+        app.edit_selected_verifier();
+        assert_eq!(app.verifier_prompt_input.text(), "cargo test");
+        app.verifier_prompt_input.set_text("cargo test --all");
+        app.verifier_kind_input = VerifierKind::Command;
+        app.add_verifier();
+        assert_eq!(app.verifiers.len(), 2);
+        assert_eq!(app.verifiers[1].prompt, "cargo test --all");
+        assert_eq!(app.verifiers[1].kind, VerifierKind::Command);
+        assert!(!app.verifiers[1].enabled);
+        assert_eq!(app.editing_verifier, None);
+
+        // The edited verifier follows a move, and cancelling leaves it unchanged.
+        app.edit_selected_verifier();
+        app.move_selected_verifier(-1);
+        app.move_selected_verifier(-1);
+        assert_eq!(app.verifiers[0].name, "tests");
+        assert_eq!(app.editing_verifier, Some(0));
+        app.cancel_edit();
+        assert_eq!(app.verifiers[0].prompt, "cargo test --all");
+        assert!(app.verifier_name_input.text().is_empty());
+    }
+
+    #[test]
+    fn working_dir_must_exist() {
+        let mut app = App::new();
+        app.prompt_input.set_text("build it");
+        app.verifiers.push(verifier("a"));
+        app.set_working_dir(Path::new("/tmp/../tmp"));
+        assert_eq!(app.working_dir(), Some(Path::new("/tmp").canonicalize().unwrap()));
+        assert!(app.can_start());
+
+        app.set_working_dir(Path::new("/definitely/not/here"));
+        assert_eq!(app.working_dir(), None);
+        assert!(!app.can_start());
+    }
+
+    #[test]
+    fn trusting_the_project_brings_its_commands_back() {
+        let project = crate::project::parse("[[verifiers]]\nname = \"tests\"\nprompt = \"cargo test\"\nkind = \"command\"\n").unwrap();
+        let mut app = App::new();
+        let mut config = Config::default();
+        project.without_commands().apply(&mut config);
+        app.load_suites(&config, DEFAULT_SUITE);
+        app.project_config = Some((PathBuf::from("/src/app/.verifiers.toml"), project.without_commands()));
+        app.trust_prompt = Some(project);
+        app.project_commands_ignored = true;
+        assert!(app.verifiers.is_empty());
+
+        let (path, trusted) = app.trust_project(Config::default()).unwrap();
+        assert_eq!(path, PathBuf::from("/src/app/.verifiers.toml"));
+        assert_eq!(trusted.commands().len(), 1);
+        assert_eq!(app.verifiers[0].name, "tests");
+        assert!(app.trust_prompt.is_none());
+        assert!(!app.project_commands_ignored);
+        assert_eq!(app.project_config.as_ref().unwrap().1.commands().len(), 1);
+    }
+
+    #[test]
+    fn relaunching_a_run_takes_its_working_dir() {
+        let mut app = App::new();
+        let options = RunOptions {
+            working_dir: PathBuf::from("/src/app"),
+            ..RunOptions::default()
+        };
+        let run = RunRecord::new("build it", DEFAULT_SUITE, Vec::new(), &BackendConfig::default(), &options, Path::new("/tmp/run.txt"));
+        app.open_history(vec![run]);
+        app.relaunch_selected_run();
+        assert_eq!(app.working_dir_input.text(), "/src/app");
+        assert_eq!(app.run_options.working_dir, PathBuf::from("/src/app"));
+    }
+
+    #[test]
+    fn relaunching_a_run_keeps_the_suites_other_verifiers() {
+        let mut app = App::new();
+        let mut disabled = verifier("bench");
+        disabled.enabled = false;
+        app.verifiers = vec![verifier("lint"), disabled, verifier("docs")];
+        let mut lint = verifier("lint");
+        lint.prompt = "no warnings".to_string();
+        lint.gate = true;
+        let options = RunOptions::default();
+        let run = RunRecord::new(
+            "build it",
+            DEFAULT_SUITE,
+            vec![lint, verifier("tests")],
+            &BackendConfig::default(),
+            &options,
+            Path::new("/tmp/run.txt"),
+        );
+        app.open_history(vec![run]);
+        app.relaunch_selected_run();
+
+        let names: Vec<(&str, bool)> = app.verifiers.iter().map(|v| (v.name.as_str(), v.enabled)).collect();
+        assert_eq!(names, vec![("lint", true), ("bench", false), ("docs", false), ("tests", true)]);
+        assert_eq!(app.verifiers[0].prompt, "no warnings");
+        assert!(app.verifiers[0].gate);
+    }
+
+    #[test]
+    fn parse_config_accepts_legacy_array() {
+        let config = parse_config(r#"[{"name": "lint", "prompt": "no warnings"}]"#).unwrap();
+        assert_eq!(config.backend, BackendConfig::Claude { model: None });
+        assert_eq!(config.options, RunOptions::default());
+        assert_eq!(config.verifiers.len(), 1);
+        assert!(config.verifiers[0].enabled);
+        assert_eq!(config.verifiers[0].kind, VerifierKind::Agent);
+    }
+
+    #[test]
+    fn parse_config_reads_command_backend() {
+        let config = parse_config(
+            r#"{"backend": {"type": "command", "command": "agent -"}, "concurrency": 2,
+                "verifiers": [{"name": "tests", "prompt": "cargo test", "kind": "command"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.backend,
+            BackendConfig::Command {
+                command: "agent -".to_string()
+            }
+        );
+        assert_eq!(config.options.concurrency, 2);
+        assert_eq!(config.verifiers[0].kind, VerifierKind::Command);
+    }
+}
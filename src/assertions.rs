@@ -0,0 +1,256 @@
+use regex::Regex;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file check run by an assertion verifier, without calling an agent.
+///
+/// Written one per line in the verifier's body:
+///
+/// ```text
+/// exists <path>
+/// matches <path> <regex>
+/// not_contains <path> <text>
+/// json <path> [<key path>]
+/// ```
+///
+/// A path with spaces goes in double quotes. The regex or text is the rest of the line.
+#[derive(Clone, Debug)]
+pub enum Assertion {
+    /// The file exists.
+    Exists(PathBuf),
+    /// The file's contents match the regex somewhere.
+    Matches(PathBuf, Regex),
+    /// The file's contents do not contain the text.
+    NotContains(PathBuf, String),
+    /// The file parses as JSON and, with a key path, has a value there.
+    Json(PathBuf, Option<KeyPath>),
+}
+
+/// A jq-style path into a JSON document, e.g. `.package.version` or `.items[0].id`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyPath {
+    text: String,
+    steps: Vec<KeyStep>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum KeyStep {
+    Key(String),
+    Index(usize),
+}
+
+impl KeyPath {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid key path '{}'", text);
+        let mut steps = Vec::new();
+        let mut rest = text.strip_prefix('.').unwrap_or(text);
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let (index, after) = after.split_once(']').ok_or_else(invalid)?;
+                steps.push(KeyStep::Index(index.parse().map_err(|_| invalid())?));
+                rest = after;
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let key = &rest[..end];
+                if key.is_empty() || key.contains(char::is_whitespace) {
+                    return Err(invalid());
+                }
+                steps.push(KeyStep::Key(key.to_string()));
+                rest = &rest[end..];
+            }
+            if let Some(after) = rest.strip_prefix('.') {
+                if after.is_empty() || after.starts_with(['.', '[']) {
+                    return Err(invalid());
+                }
+                rest = after;
+            }
+        }
+        if steps.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            text: text.to_string(),
+            steps,
+        })
+    }
+
+    /// The value at this path, if there is one.
+    fn lookup<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.steps.iter().try_fold(value, |value, step| match step {
+            KeyStep::Key(key) => value.get(key),
+            KeyStep::Index(index) => value.get(index),
+        })
+    }
+}
+
+/// Parse an assertion verifier's body. Blank lines and `#` comments are skipped.
+pub fn parse_assertions(body: &str) -> Result<Vec<Assertion>, String> {
+    let assertions: Vec<Assertion> = body
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_assertion)
+        .collect::<Result<_, _>>()?;
+    if assertions.is_empty() {
+        return Err("no assertions given".to_string());
+    }
+    Ok(assertions)
+}
+
+fn parse_assertion(line: &str) -> Result<Assertion, String> {
+    let (op, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let (path, arg) = split_path(rest.trim()).ok_or_else(|| format!("unterminated quote in '{}'", line))?;
+    if path.is_empty() {
+        return Err(format!("missing path in '{}'", line));
+    }
+    let path = PathBuf::from(path);
+    match (op, arg.is_empty()) {
+        ("exists", true) => Ok(Assertion::Exists(path)),
+        ("json", true) => Ok(Assertion::Json(path, None)),
+        ("json", false) => KeyPath::parse(arg)
+            .map(|key| Assertion::Json(path, Some(key)))
+            .map_err(|e| format!("{} in '{}'", e, line)),
+        ("matches", false) => Regex::new(arg)
+            .map(|re| Assertion::Matches(path, re))
+            .map_err(|e| format!("invalid regex in '{}': {}", line, e)),
+        ("not_contains", false) => Ok(Assertion::NotContains(path, arg.to_string())),
+        ("exists", false) => Err(format!("unexpected argument in '{}'", line)),
+        ("matches" | "not_contains", true) => Err(format!("missing argument in '{}'", line)),
+        _ => Err(format!(
+            "unknown assertion '{}' (expected exists, matches, not_contains or json)",
+            op
+        )),
+    }
+}
+
+/// Split the path off the front of `rest`, unquoting it if it is in double quotes
+/// (where `\"` and `\\` stand for `"` and `\`). Returns the path and the trimmed
+/// rest of the line, or `None` if a quote isn't closed.
+fn split_path(rest: &str) -> Option<(String, &str)> {
+    let Some(quoted) = rest.strip_prefix('"') else {
+        let (path, arg) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        return Some((path.to_string(), arg.trim()));
+    };
+    let mut path = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((path, quoted[i + 1..].trim())),
+            '\\' => path.push(chars.next()?.1),
+            c => path.push(c),
+        }
+    }
+    None
+}
+
+impl Assertion {
+    /// Check the assertion, resolving relative paths against `dir`. Returns why it
+    /// failed, if it did.
+    pub fn check(&self, dir: &Path) -> Option<String> {
+        match self {
+            Assertion::Exists(path) => {
+                (!dir.join(path).exists()).then(|| format!("{} does not exist", path.display()))
+            }
+            Assertion::Matches(path, re) => match read(dir, path) {
+                Ok(contents) if re.is_match(&contents) => None,
+                Ok(_) => Some(format!("{} does not match /{}/", path.display(), re)),
+                Err(e) => Some(e),
+            },
+            Assertion::NotContains(path, text) => match read(dir, path) {
+                Ok(contents) => contents.lines().position(|line| line.contains(text.as_str())).map(|i| {
+                    format!("{} contains {:?} (line {})", path.display(), text, i + 1)
+                }),
+                Err(e) => Some(e),
+            },
+            Assertion::Json(path, key) => match read(dir, path).and_then(|contents| {
+                serde_json::from_str::<Value>(&contents)
+                    .map_err(|e| format!("{} is not valid JSON: {}", path.display(), e))
+            }) {
+                Ok(value) => key
+                    .as_ref()
+                    .filter(|key| key.lookup(&value).is_none())
+                    .map(|key| format!("{} has no value at {}", path.display(), key.text)),
+                Err(e) => Some(e),
+            },
+        }
+    }
+}
+
+fn read(dir: &Path, path: &Path) -> Result<String, String> {
+    fs::read_to_string(dir.join(path)).map_err(|e| format!("cannot read {}: {}", path.display(), e))
+}
+
+/// Parse and check every assertion in `body`. Returns the reasons for each failure;
+/// empty means they all held.
+pub fn check_all(body: &str, dir: &Path) -> Result<Vec<String>, String> {
+    Ok(parse_assertions(body)?
+        .iter()
+        .filter_map(|assertion| assertion.check(dir))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn rejects_malformed_assertions() {
+        assert!(parse_assertions("").is_err());
+        assert!(parse_assertions("exists").is_err());
+        assert!(parse_assertions("exists a b").is_err());
+        assert!(parse_assertions("matches a").is_err());
+        assert!(parse_assertions("matches a (").is_err());
+        assert!(parse_assertions("smells a").is_err());
+        assert!(parse_assertions("exists \"a b").is_err());
+        assert!(parse_assertions("json a .b..c").is_err());
+        assert!(parse_assertions("json a [x]").is_err());
+        assert!(parse_assertions("json a .b c").is_err());
+        assert_eq!(parse_assertions("# files\nexists a\n\njson b").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn quoted_paths_may_hold_spaces() {
+        let assertions = parse_assertions("exists \"My Documents/a.txt\"\nnot_contains \"x \\\"y\\\".md\" two words").unwrap();
+        assert!(matches!(&assertions[0], Assertion::Exists(path) if path == Path::new("My Documents/a.txt")));
+        assert!(matches!(
+            &assertions[1],
+            Assertion::NotContains(path, text) if path == Path::new("x \"y\".md") && text == "two words"
+        ));
+    }
+
+    #[test]
+    fn key_paths_step_through_objects_and_arrays() {
+        let value: Value = serde_json::from_str(r#"{"package": {"name": "app"}, "items": [{"id": 7}]}"#).unwrap();
+        let lookup = |path: &str| KeyPath::parse(path).unwrap().lookup(&value).cloned();
+        assert_eq!(lookup(".package.name"), Some(Value::from("app")));
+        assert_eq!(lookup("items[0].id"), Some(Value::from(7)));
+        assert_eq!(lookup(".items[1]"), None);
+        assert_eq!(lookup(".package.version"), None);
+        assert!(KeyPath::parse(".").is_err());
+    }
+
+    #[test]
+    fn checks_files_relative_to_dir() {
+        let dir = std::env::temp_dir().join(format!("verifiers_assert_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}\n// TODO: tests\n").unwrap();
+        fs::write(dir.join("ok.json"), r#"{"a": 1}"#).unwrap();
+        fs::write(dir.join("bad.json"), "{").unwrap();
+
+        let body = "exists main.rs\nmatches main.rs fn\\s+main\njson ok.json\njson ok.json .a";
+        assert_eq!(check_all(body, &dir), Ok(Vec::new()));
+
+        let body = "exists missing.rs\nmatches main.rs ^struct\nnot_contains main.rs TODO\njson bad.json\njson ok.json .b";
+        let failures = check_all(body, &dir).unwrap();
+        assert_eq!(failures.len(), 5);
+        assert_eq!(failures[0], "missing.rs does not exist");
+        assert_eq!(failures[1], "main.rs does not match /^struct/");
+        assert_eq!(failures[2], "main.rs contains \"TODO\" (line 2)");
+        assert!(failures[3].starts_with("bad.json is not valid JSON"));
+        assert_eq!(failures[4], "ok.json has no value at .b");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
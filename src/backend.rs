@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::process::Command;
+use uuid::Uuid;
+
+#[cfg(test)]
+pub mod mock;
+
+/// Boxed future returned by `AgentBackend::run`, resolving to the agent's stdout.
+pub type AgentFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
+
+/// Which agent a request is being made for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentRole {
+    Worker,
+    Verifier(String),
+}
+
+/// A single agent invocation made by the runner.
+#[derive(Clone, Debug)]
+pub struct AgentRequest {
+    pub role: AgentRole,
+    pub iteration: u32,
+    pub file_path: PathBuf,
+    pub prompt: String,
+    /// The project directory the agent runs in.
+    pub working_dir: PathBuf,
+}
+
+/// Something that can run an agent prompt to completion and return its stdout.
+pub trait AgentBackend: Send + Sync {
+    /// Short human-readable name used in log and error messages.
+    fn name(&self) -> &str;
+
+    fn run<'a>(&'a self, request: &'a AgentRequest) -> AgentFuture<'a>;
+}
+
+/// Which backend to use, as stored in `verifiers.json`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackendConfig {
+    /// The Claude CLI (`claude --dangerously-skip-permissions -p -`).
+    Claude {
+        /// Passed as `--model`; the CLI's default model if unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+    },
+    /// Any shell command that reads the prompt on stdin and does the work.
+    Command { command: String },
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig::Claude { model: None }
+    }
+}
+
+impl BackendConfig {
+    pub fn build(&self) -> Arc<dyn AgentBackend> {
+        match self {
+            BackendConfig::Claude { model } => Arc::new(ClaudeBackend::new(model.as_deref())),
+            BackendConfig::Command { command } => Arc::new(CommandBackend::new(command.clone())),
+        }
+    }
+}
+
+/// Runs prompts through the Claude CLI.
+pub struct ClaudeBackend {
+    command: String,
+}
+
+impl ClaudeBackend {
+    pub fn new(model: Option<&str>) -> Self {
+        let mut command = "claude --dangerously-skip-permissions -p -".to_string();
+        if let Some(model) = model {
+            command.push_str(&format!(" --model '{}'", model.replace('\'', "'\\''")));
+        }
+        Self { command }
+    }
+}
+
+impl AgentBackend for ClaudeBackend {
+    fn name(&self) -> &str {
+        "claude"
+    }
+
+    fn run<'a>(&'a self, request: &'a AgentRequest) -> AgentFuture<'a> {
+        Box::pin(run_shell(&self.command, self.name(), request))
+    }
+}
+
+/// Runs prompts through an arbitrary shell command, fed on stdin.
+pub struct CommandBackend {
+    command: String,
+}
+
+impl CommandBackend {
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+impl AgentBackend for CommandBackend {
+    fn name(&self) -> &str {
+        &self.command
+    }
+
+    fn run<'a>(&'a self, request: &'a AgentRequest) -> AgentFuture<'a> {
+        Box::pin(run_shell(&self.command, self.name(), request))
+    }
+}
+
+/// Write a prompt string to a temp file and return the path.
+fn write_prompt_file(prompt: &str) -> std::io::Result<String> {
+    let path = format!("/tmp/verifiers_prompt_{}.txt", Uuid::new_v4());
+    fs::write(&path, prompt)?;
+    Ok(path)
+}
+
+/// Remove a temp prompt file (best effort).
+fn cleanup_prompt_file(path: &str) {
+    let _ = fs::remove_file(path);
+}
+
+/// Cleans up after an agent process. If the call is dropped before the process exits
+/// (timeout, cancellation, quitting the TUI), kills the agent's whole process group so
+/// no stray agents keep running.
+struct AgentGuard {
+    prompt_file: Option<String>,
+    process_group: Option<u32>,
+}
+
+impl Drop for AgentGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.process_group {
+            kill_process_group(pgid);
+        }
+        if let Some(prompt_file) = &self.prompt_file {
+            cleanup_prompt_file(prompt_file);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(pgid: u32) {
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pgid: u32) {}
+
+/// Run `cat {prompt_file} | {command}` under bash and return stdout.
+///
+/// The request context is exported as `VERIFIERS_ROLE`, `VERIFIERS_ITERATION` and
+/// `VERIFIERS_FILE` so wrapper scripts can tell agents apart.
+async fn run_shell(command: &str, name: &str, request: &AgentRequest) -> Result<String, String> {
+    let prompt_file =
+        write_prompt_file(&request.prompt).map_err(|e| format!("Failed to write prompt file: {}", e))?;
+    let script = format!("cat '{}' | {}", prompt_file, command);
+    let mut guard = AgentGuard {
+        prompt_file: Some(prompt_file),
+        process_group: None,
+    };
+    let result = run_bash(&script, name, request, &mut guard).await?;
+
+    if result.status.success() {
+        Ok(String::from_utf8_lossy(&result.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        Err(format!(
+            "{} exited with {}: stdout={}, stderr={}",
+            name, result.status, stdout, stderr
+        ))
+    }
+}
+
+/// The result of a command verifier's shell command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandOutput {
+    pub success: bool,
+    /// How the command exited, e.g. "exit status: 1".
+    pub status: String,
+    /// stdout and stderr, interleaved as the command wrote them.
+    pub output: String,
+}
+
+/// Run a command verifier's shell command. Unlike agent calls, a non-zero exit is a
+/// result rather than an error; `Err` means the command could not be run at all.
+pub async fn run_command(command: &str, request: &AgentRequest) -> Result<CommandOutput, String> {
+    // The newline keeps a trailing comment in `command` from swallowing the brace.
+    let script = format!("{{ {}\n}} 2>&1", command);
+    let mut guard = AgentGuard {
+        prompt_file: None,
+        process_group: None,
+    };
+    let result = run_bash(&script, command, request, &mut guard).await?;
+    Ok(CommandOutput {
+        success: result.status.success(),
+        status: result.status.to_string(),
+        output: String::from_utf8_lossy(&result.stdout).to_string(),
+    })
+}
+
+/// Spawn `script` under bash in its own process group and wait for it, with the request
+/// context in its environment. `guard` kills the process group if this is dropped early.
+async fn run_bash(
+    script: &str,
+    name: &str,
+    request: &AgentRequest,
+    guard: &mut AgentGuard,
+) -> Result<std::process::Output, String> {
+    let role = match &request.role {
+        AgentRole::Worker => "worker".to_string(),
+        AgentRole::Verifier(name) => format!("verifier:{}", name),
+    };
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg(script)
+        .env("VERIFIERS_ROLE", role)
+        .env("VERIFIERS_ITERATION", request.iteration.to_string())
+        .env("VERIFIERS_FILE", &request.file_path)
+        .current_dir(&request.working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Own process group, so the agent and everything it spawns can be killed together.
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let child = cmd.spawn().map_err(|e| format!("Failed to spawn {}: {}", name, e))?;
+    guard.process_group = child.id();
+    let result = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to wait for {}: {}", name, e))?;
+    guard.process_group = None;
+    Ok(result)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn dropping_a_call_kills_the_agent_process_group() {
+        let pid_file = format!("/tmp/verifiers_test_pid_{}", Uuid::new_v4());
+        // The agent backgrounds a grandchild, so killing bash alone would leak it.
+        let backend = CommandBackend::new(format!("sleep 30 & echo $! > {}; wait", pid_file));
+        let request = AgentRequest {
+            role: AgentRole::Worker,
+            iteration: 1,
+            file_path: PathBuf::from("/dev/null"),
+            prompt: String::new(),
+            working_dir: PathBuf::from("/tmp"),
+        };
+
+        let result = tokio::time::timeout(Duration::from_millis(500), backend.run(&request)).await;
+        assert!(result.is_err());
+
+        let pid: libc::pid_t = fs::read_to_string(&pid_file).unwrap().trim().parse().unwrap();
+        let _ = fs::remove_file(&pid_file);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // Gone, or a zombie waiting to be reaped by init.
+        let alive = fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false);
+        assert!(!alive, "agent grandchild {} survived cancellation", pid);
+    }
+
+    #[tokio::test]
+    async fn command_output_is_captured_and_failure_is_not_an_error() {
+        let request = AgentRequest {
+            role: AgentRole::Verifier("tests".to_string()),
+            iteration: 2,
+            file_path: PathBuf::from("/dev/null"),
+            prompt: String::new(),
+            working_dir: PathBuf::from("/tmp"),
+        };
+
+        let passed = run_command("echo $VERIFIERS_ROLE in $PWD # trailing comment", &request).await.unwrap();
+        assert!(passed.success);
+        assert_eq!(passed.output, "verifier:tests in /tmp\n");
+
+        let failed = run_command("echo out; echo err >&2; exit 3", &request).await.unwrap();
+        assert!(!failed.success);
+        assert_eq!(failed.status, "exit status: 3");
+        assert_eq!(failed.output, "out\nerr\n");
+    }
+}
@@ -0,0 +1,116 @@
+use super::{AgentBackend, AgentFuture, AgentRequest, AgentRole};
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A scripted edit a mock agent performs on the shared file.
+#[derive(Clone, Debug)]
+pub enum MockAction {
+    /// Append text to the end of the file.
+    Append(String),
+    /// Change `[] name` to `[x] name` for the calling verifier.
+    Check,
+    /// Replace the first occurrence of the first string with the second.
+    Replace(String, String),
+    /// Write a file, relative to the working directory, as a worker changing the project would.
+    WriteFile(String, String),
+    /// Print this text to stdout (e.g. a JSON verdict).
+    Reply(String),
+    /// Make the agent call itself fail with this message.
+    Fail(String),
+    /// Sleep before carrying on with the remaining steps, e.g. to simulate a hung agent.
+    Delay(Duration),
+}
+
+#[derive(Clone, Debug)]
+struct MockStep {
+    role: AgentRole,
+    iteration: Option<u32>,
+    action: MockAction,
+}
+
+/// A backend that replays scripted file edits instead of calling a real agent.
+///
+/// Steps match on the calling agent's role and, optionally, the iteration number.
+/// Every matching step is applied in the order it was added; calls with no matching
+/// step leave the file untouched.
+#[derive(Default)]
+pub struct MockBackend {
+    steps: Vec<MockStep>,
+    calls: Mutex<Vec<(AgentRole, u32)>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Perform `action` whenever `role` is called (on `iteration`, or on every iteration if `None`).
+    pub fn on(mut self, role: AgentRole, iteration: Option<u32>, action: MockAction) -> Self {
+        self.steps.push(MockStep { role, iteration, action });
+        self
+    }
+
+    pub fn worker(self, iteration: Option<u32>, action: MockAction) -> Self {
+        self.on(AgentRole::Worker, iteration, action)
+    }
+
+    pub fn verifier(self, name: &str, iteration: Option<u32>, action: MockAction) -> Self {
+        self.on(AgentRole::Verifier(name.to_string()), iteration, action)
+    }
+
+    /// Every call made so far, as (role, iteration).
+    pub fn calls(&self) -> Vec<(AgentRole, u32)> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    async fn apply(&self, request: &AgentRequest) -> Result<String, String> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((request.role.clone(), request.iteration));
+
+        let steps = self.steps.iter().filter(|step| {
+            step.role == request.role && step.iteration.is_none_or(|i| i == request.iteration)
+        });
+        let mut stdout = String::new();
+        for step in steps {
+            match &step.action {
+                MockAction::Append(text) => {
+                    let mut contents = fs::read_to_string(&request.file_path).map_err(|e| e.to_string())?;
+                    contents.push_str(text);
+                    fs::write(&request.file_path, contents).map_err(|e| e.to_string())?;
+                }
+                MockAction::Check => {
+                    let AgentRole::Verifier(name) = &request.role else {
+                        return Err("only verifiers can check a box".to_string());
+                    };
+                    let contents = fs::read_to_string(&request.file_path).map_err(|e| e.to_string())?;
+                    let updated = contents.replacen(&format!("[] {}\n", name), &format!("[x] {}\n", name), 1);
+                    fs::write(&request.file_path, updated).map_err(|e| e.to_string())?;
+                }
+                MockAction::Replace(from, to) => {
+                    let contents = fs::read_to_string(&request.file_path).map_err(|e| e.to_string())?;
+                    fs::write(&request.file_path, contents.replacen(from, to, 1)).map_err(|e| e.to_string())?;
+                }
+                MockAction::WriteFile(path, contents) => {
+                    fs::write(request.working_dir.join(path), contents).map_err(|e| e.to_string())?;
+                }
+                MockAction::Reply(text) => stdout.push_str(text),
+                MockAction::Fail(message) => return Err(message.clone()),
+                MockAction::Delay(duration) => tokio::time::sleep(*duration).await,
+            }
+        }
+        Ok(stdout)
+    }
+}
+
+impl AgentBackend for MockBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn run<'a>(&'a self, request: &'a AgentRequest) -> AgentFuture<'a> {
+        Box::pin(self.apply(request))
+    }
+}
@@ -0,0 +1,301 @@
+use crate::git::GitMode;
+use crate::retry::RetryPolicy;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage:
+  verifiers [--cwd <dir>] [--suite <name>]
+                            Start the interactive TUI
+  verifiers run [OPTIONS]   Run the worker/verifier loop headless
+  verifiers resume <run-id> [--json]
+                            Continue an interrupted run from its last completed step
+
+Run options:
+  --prompt-file <path>      File containing the worker prompt (required)
+  --cwd <dir>               Project directory the agents run in (default: current directory)
+  --verifiers <path>        verifiers.json-style file to use instead of the saved config
+  --suite <name>            Verifier suite to run (default: the default suite)
+  --concurrency <n>         Maximum number of verifiers to run at once
+  --max-iterations <n>      Give up after n iterations (0 = no limit)
+  --time-budget <secs>      Start no new iteration after this many seconds (0 = no limit)
+  --worker-timeout <secs>   Kill a worker call after this many seconds (0 = no limit)
+  --verifier-timeout <secs> Kill a verifier call after this many seconds (0 = no limit)
+  --git <off|branch|worktree>
+                            Commit the worker's changes to a new branch every iteration,
+                            in place or in a separate worktree
+  --git-reset-on <names>    Comma-separated verifiers; when one fails, reset the branch to
+                            the last commit where they all passed
+  --retry <all|failed|affected>
+                            Which verifiers run again after a failed iteration: all of
+                            them, the failed ones, or also those whose files changed
+  --fail-fast               End an iteration at the first failing verifier, or the first
+                            failing gate if any verifier is marked as one
+  --trust-project           Allow the project's .verifiers.toml to run commands, and
+                            remember that it is trusted
+  --json                    Also write every runner event to stdout as JSON Lines
+
+Exit codes (run, resume): 0 all verifiers passed, 1 out of iterations or time, 2 error, 130 interrupted";
+
+/// What the binary was asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Start the TUI, optionally with the working directory filled in and a suite picked.
+    Tui { cwd: Option<PathBuf>, suite: Option<String> },
+    Run(RunArgs),
+    Resume(ResumeArgs),
+    Help,
+}
+
+/// Arguments for `verifiers run`.
+#[derive(Debug, Default, PartialEq)]
+pub struct RunArgs {
+    pub prompt_file: PathBuf,
+    pub cwd: Option<PathBuf>,
+    pub verifiers_file: Option<PathBuf>,
+    pub suite: Option<String>,
+    pub concurrency: Option<usize>,
+    pub max_iterations: Option<u32>,
+    pub time_budget_secs: Option<u64>,
+    pub worker_timeout_secs: Option<u64>,
+    pub verifier_timeout_secs: Option<u64>,
+    pub git: Option<GitMode>,
+    pub git_reset_on: Option<Vec<String>>,
+    pub retry: Option<RetryPolicy>,
+    pub fail_fast: bool,
+    pub trust_project: bool,
+    pub json: bool,
+}
+
+/// Arguments for `verifiers resume`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ResumeArgs {
+    pub run_id: String,
+    pub json: bool,
+}
+
+/// Parse command-line arguments (without the program name).
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(String::as_str) {
+        None => Ok(Command::Tui { cwd: None, suite: None }),
+        Some("-h" | "--help" | "help") => Ok(Command::Help),
+        Some("run") => {
+            args.next();
+            parse_run(args).map(Command::Run)
+        }
+        Some("resume") => {
+            args.next();
+            parse_resume(args).map(Command::Resume)
+        }
+        Some(flag) if flag.starts_with("--") => parse_tui(args),
+        Some(other) => Err(format!("Unknown command '{}'", other)),
+    }
+}
+
+/// Split `--flag=value` into its parts; anything else is a flag with no inline value.
+fn split_flag(arg: String) -> (String, Option<String>) {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+        _ => (arg, None),
+    }
+}
+
+fn parse_tui<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut cwd = None;
+    let mut suite = None;
+    while let Some(arg) = args.next() {
+        let (flag, inline) = split_flag(arg);
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("Missing value for {}", flag))
+        };
+        match flag.as_str() {
+            "--cwd" => cwd = Some(PathBuf::from(value()?)),
+            "--suite" => suite = Some(value()?),
+            _ => return Err(format!("Unknown option '{}'", flag)),
+        }
+    }
+    Ok(Command::Tui { cwd, suite })
+}
+
+fn parse_run<I: Iterator<Item = String>>(mut args: I) -> Result<RunArgs, String> {
+    let mut prompt_file = None;
+    let mut run_args = RunArgs::default();
+
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline) = split_flag(arg);
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("Missing value for {}", flag))
+        };
+        match flag.as_str() {
+            "--prompt-file" => prompt_file = Some(PathBuf::from(value()?)),
+            "--cwd" => run_args.cwd = Some(PathBuf::from(value()?)),
+            "--verifiers" => run_args.verifiers_file = Some(PathBuf::from(value()?)),
+            "--suite" => run_args.suite = Some(value()?),
+            "--concurrency" => run_args.concurrency = Some(parse_number(&flag, &value()?)?),
+            "--max-iterations" => run_args.max_iterations = Some(parse_number(&flag, &value()?)?),
+            "--time-budget" => run_args.time_budget_secs = Some(parse_number(&flag, &value()?)?),
+            "--worker-timeout" => run_args.worker_timeout_secs = Some(parse_number(&flag, &value()?)?),
+            "--verifier-timeout" => run_args.verifier_timeout_secs = Some(parse_number(&flag, &value()?)?),
+            "--git" => {
+                let mode = value()?;
+                run_args.git = Some(
+                    GitMode::ALL
+                        .into_iter()
+                        .find(|m| m.label() == mode)
+                        .ok_or_else(|| format!("Invalid value '{}' for --git (expected off, branch or worktree)", mode))?,
+                );
+            }
+            "--git-reset-on" => {
+                let names = value()?;
+                run_args.git_reset_on = Some(
+                    names
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect(),
+                );
+            }
+            "--retry" => {
+                let policy = value()?;
+                run_args.retry = Some(
+                    RetryPolicy::ALL
+                        .into_iter()
+                        .find(|p| p.label() == policy)
+                        .ok_or_else(|| {
+                            format!("Invalid value '{}' for --retry (expected all, failed or affected)", policy)
+                        })?,
+                );
+            }
+            "--fail-fast" if inline.is_none() => run_args.fail_fast = true,
+            "--trust-project" if inline.is_none() => run_args.trust_project = true,
+            "--json" if inline.is_none() => run_args.json = true,
+            _ => return Err(format!("Unknown option '{}'", flag)),
+        }
+    }
+
+    run_args.prompt_file = prompt_file.ok_or("Missing required option --prompt-file")?;
+    Ok(run_args)
+}
+
+fn parse_resume<I: Iterator<Item = String>>(args: I) -> Result<ResumeArgs, String> {
+    let mut resume_args = ResumeArgs::default();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => resume_args.json = true,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            _ if resume_args.run_id.is_empty() => resume_args.run_id = arg,
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+    if resume_args.run_id.is_empty() {
+        return Err("Missing run id (see the History screen)".to_string());
+    }
+    Ok(resume_args)
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_starts_tui() {
+        assert_eq!(parse_str(&[]), Ok(Command::Tui { cwd: None, suite: None }));
+        assert_eq!(
+            parse_str(&["--cwd=../app", "--suite", "frontend"]),
+            Ok(Command::Tui {
+                cwd: Some(PathBuf::from("../app")),
+                suite: Some("frontend".to_string()),
+            })
+        );
+        assert!(parse_str(&["--cwd"]).is_err());
+    }
+
+    #[test]
+    fn parses_run_options() {
+        assert_eq!(
+            parse_str(&[
+                "run",
+                "--prompt-file",
+                "task.md",
+                "--cwd",
+                "/src/app",
+                "--verifiers=team.json",
+                "--suite=backend",
+                "--concurrency",
+                "3",
+                "--max-iterations=0",
+                "--time-budget",
+                "3600",
+                "--verifier-timeout",
+                "300",
+                "--git",
+                "branch",
+                "--git-reset-on=tests, lint",
+                "--retry",
+                "affected",
+                "--fail-fast",
+                "--trust-project",
+                "--json",
+            ]),
+            Ok(Command::Run(RunArgs {
+                prompt_file: PathBuf::from("task.md"),
+                cwd: Some(PathBuf::from("/src/app")),
+                verifiers_file: Some(PathBuf::from("team.json")),
+                suite: Some("backend".to_string()),
+                concurrency: Some(3),
+                max_iterations: Some(0),
+                time_budget_secs: Some(3600),
+                worker_timeout_secs: None,
+                verifier_timeout_secs: Some(300),
+                git: Some(GitMode::Branch),
+                git_reset_on: Some(vec!["tests".to_string(), "lint".to_string()]),
+                retry: Some(RetryPolicy::Affected),
+                fail_fast: true,
+                trust_project: true,
+                json: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_resume() {
+        assert_eq!(
+            parse_str(&["resume", "1a2b3c4d", "--json"]),
+            Ok(Command::Resume(ResumeArgs {
+                run_id: "1a2b3c4d".to_string(),
+                json: true,
+            }))
+        );
+        assert!(parse_str(&["resume"]).is_err());
+        assert!(parse_str(&["resume", "1a2b3c4d", "5e6f7a8b"]).is_err());
+        assert!(parse_str(&["resume", "1a2b3c4d", "--cwd", "."]).is_err());
+    }
+
+    #[test]
+    fn run_requires_prompt_file() {
+        assert!(parse_str(&["run", "--verifiers", "team.json"]).is_err());
+        assert!(parse_str(&["run", "--prompt-file"]).is_err());
+        assert!(parse_str(&["run", "--bogus"]).is_err());
+        assert!(parse_str(&["run", "--prompt-file", "t.md", "--concurrency", "many"]).is_err());
+        assert!(parse_str(&["run", "--prompt-file", "t.md", "--git", "yes"]).is_err());
+        assert!(parse_str(&["run", "--prompt-file", "t.md", "--retry", "some"]).is_err());
+    }
+}
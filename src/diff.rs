@@ -0,0 +1,218 @@
+/// One line of a line-by-line diff.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+    /// A run of unchanged lines left out by `with_context`.
+    Skipped(usize),
+}
+
+/// Most edits `diff_lines` looks for between two files before it shows the changed
+/// stretch as replaced. Bounds the time and memory one diff takes, whatever the files.
+const MAX_EDITS: usize = 1000;
+
+/// Line diff of `old` against `new`: a shortest edit script, found with Myers'
+/// algorithm after matching the common prefix and suffix. If the files differ by
+/// more than `MAX_EDITS` lines, everything between the common prefix and suffix is
+/// shown as removed and added instead.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().map(|l| DiffLine::Same(l.to_string())).collect();
+    match edit_script(a, b, MAX_EDITS) {
+        Some(edits) => lines.extend(edits),
+        None => {
+            lines.extend(a.iter().map(|l| DiffLine::Removed(l.to_string())));
+            lines.extend(b.iter().map(|l| DiffLine::Added(l.to_string())));
+        }
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|l| DiffLine::Same(l.to_string())));
+    lines
+}
+
+/// Myers' shortest edit script from `a` to `b`, or `None` if it needs more than
+/// `max_edits` removed and added lines.
+fn edit_script(a: &[&str], b: &[&str], max_edits: usize) -> Option<Vec<DiffLine>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m).min(max_edits as isize);
+    // v[k + offset]: the furthest x reached on diagonal k = x - y.
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // trace[d]: v over diagonals -d-1..=d+1 as it was before round d, to walk back.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut found = false;
+    'rounds: for d in 0..=max {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'rounds;
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(DiffLine::Same(a[x as usize].to_string()));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(DiffLine::Added(b[prev_y as usize].to_string()));
+            } else {
+                edits.push(DiffLine::Removed(a[prev_x as usize].to_string()));
+            }
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    edits.reverse();
+    Some(edits)
+}
+
+/// Keep `context` unchanged lines around each change and collapse the rest.
+pub fn with_context(lines: Vec<DiffLine>, context: usize) -> Vec<DiffLine> {
+    let mut keep = vec![false; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        if !matches!(line, DiffLine::Same(_)) {
+            let end = (i + context).min(lines.len() - 1);
+            keep[i.saturating_sub(context)..=end].fill(true);
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut skipped = 0;
+    for (line, keep) in lines.into_iter().zip(keep) {
+        if !keep {
+            skipped += 1;
+            continue;
+        }
+        if skipped > 0 {
+            result.push(DiffLine::Skipped(skipped));
+            skipped = 0;
+        }
+        result.push(line);
+    }
+    if skipped > 0 {
+        result.push(DiffLine::Skipped(skipped));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same(s: &str) -> DiffLine {
+        DiffLine::Same(s.to_string())
+    }
+
+    #[test]
+    fn diffs_changed_lines() {
+        let old = "[] lint\n[] tests\n\ntask\n";
+        let new = "[x] lint\n[] tests\n\ntask\n=== worker ===\ndone\n";
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Removed("[] lint".to_string()),
+                DiffLine::Added("[x] lint".to_string()),
+                same("[] tests"),
+                same(""),
+                same("task"),
+                DiffLine::Added("=== worker ===".to_string()),
+                DiffLine::Added("done".to_string()),
+            ]
+        );
+        assert!(diff_lines(old, old).iter().all(|line| matches!(line, DiffLine::Same(_))));
+    }
+
+    #[test]
+    fn diffs_interleaved_changes() {
+        assert_eq!(
+            diff_lines("a\nb\nc\na\nb\nb\na\n", "c\nb\na\nb\na\nc\n"),
+            vec![
+                DiffLine::Removed("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                same("c"),
+                DiffLine::Added("b".to_string()),
+                same("a"),
+                same("b"),
+                DiffLine::Removed("b".to_string()),
+                same("a"),
+                DiffLine::Added("c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn diffs_a_step_of_a_long_file() {
+        let body: String = (0..10_000).map(|i| format!("line {}\n", i)).collect();
+        let old = format!("[] lint\n{}", body);
+        let new = format!("[x] lint\n{}=== lint ===\nfix it\n", body);
+        let lines = diff_lines(&old, &new);
+        assert_eq!(lines.len(), 10_004);
+        assert_eq!(lines[0], DiffLine::Removed("[] lint".to_string()));
+        assert_eq!(lines[1], DiffLine::Added("[x] lint".to_string()));
+        assert!(lines[2..10_002].iter().all(|line| matches!(line, DiffLine::Same(_))));
+    }
+
+    #[test]
+    fn shows_large_rewrites_as_replaced() {
+        let old: String = (0..3000).map(|i| format!("old {}\n", i)).collect();
+        let new: String = (0..3000).map(|i| format!("new {}\n", i)).collect();
+        let lines = diff_lines(&format!("top\n{}end\n", old), &format!("top\n{}end\n", new));
+        assert_eq!(lines.len(), 6002);
+        assert_eq!(lines[0], same("top"));
+        assert!(lines[1..3001].iter().all(|line| matches!(line, DiffLine::Removed(_))));
+        assert!(lines[3001..6001].iter().all(|line| matches!(line, DiffLine::Added(_))));
+        assert_eq!(lines[6001], same("end"));
+    }
+
+    #[test]
+    fn collapses_unchanged_lines_away_from_changes() {
+        let old = "a\nb\nc\nd\ne\nf\n";
+        let new = "a\nb\nc\nd\ne\nF\n";
+        assert_eq!(
+            with_context(diff_lines(old, new), 1),
+            vec![
+                DiffLine::Skipped(4),
+                same("e"),
+                DiffLine::Removed("f".to_string()),
+                DiffLine::Added("F".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::process::Command;
+use uuid::Uuid;
+
+/// The user's editor: `$VISUAL`, then `$EDITOR`, then `vi`.
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Open `text` in the user's editor and return what was saved. The caller must
+/// release the terminal first.
+pub fn edit_text(text: &str) -> Result<String, String> {
+    edit_text_with(&editor_command(), text)
+}
+
+/// Like `edit_text`, with an explicit editor command. It runs through `sh`, so it may
+/// carry arguments (`code --wait`); the temp file's path is appended.
+pub fn edit_text_with(editor: &str, text: &str) -> Result<String, String> {
+    let path = std::env::temp_dir().join(format!("verifiers_edit_{}.md", Uuid::new_v4()));
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    let result = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&path)
+            .map(|contents| contents.trim_end_matches(['\n', '\r']).to_string())
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        Ok(status) => Err(format!("Editor '{}' exited with {}", editor, status)),
+        Err(e) => Err(format!("Failed to start editor '{}': {}", editor, e)),
+    };
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_what_the_editor_saved() {
+        let edited = edit_text_with("printf 'line one\\nline two\\n' >", "draft").unwrap();
+        assert_eq!(edited, "line one\nline two");
+
+        // A failing editor leaves the text alone.
+        assert!(edit_text_with("false", "draft").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::app::RunnerMessage;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One line of the JSON Lines event stream.
+#[derive(Serialize)]
+struct Event<'a> {
+    timestamp_ms: u128,
+    iteration: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verifier: Option<&'a str>,
+    #[serde(flatten)]
+    message: &'a RunnerMessage,
+}
+
+/// Turns runner messages into JSON lines, tracking the current iteration and
+/// resolving verifier indices to names.
+pub struct EventWriter {
+    verifier_names: Vec<String>,
+    iteration: u32,
+}
+
+impl EventWriter {
+    pub fn new(verifier_names: Vec<String>) -> Self {
+        Self {
+            verifier_names,
+            iteration: 0,
+        }
+    }
+
+    pub fn json_line(&mut self, msg: &RunnerMessage) -> String {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        self.json_line_at(msg, timestamp_ms)
+    }
+
+    fn json_line_at(&mut self, msg: &RunnerMessage, timestamp_ms: u128) -> String {
+        if let RunnerMessage::IterationStart(n) = msg {
+            self.iteration = *n;
+        }
+        let verifier = match msg {
+            RunnerMessage::VerifierStatusUpdate { index, .. } | RunnerMessage::VerifierFeedback { index, .. } => {
+                self.verifier_names.get(*index).map(String::as_str)
+            }
+            _ => None,
+        };
+        let event = Event {
+            timestamp_ms,
+            iteration: self.iteration,
+            verifier,
+            message: msg,
+        };
+        serde_json::to_string(&event).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::VerifierStatus;
+
+    #[test]
+    fn events_carry_iteration_and_verifier_name() {
+        let mut writer = EventWriter::new(vec!["lint".to_string(), "tests".to_string()]);
+        assert_eq!(
+            writer.json_line_at(&RunnerMessage::IterationStart(2), 7),
+            r#"{"timestamp_ms":7,"iteration":2,"type":"iteration_start","data":2}"#
+        );
+        assert_eq!(
+            writer.json_line_at(
+                &RunnerMessage::VerifierStatusUpdate {
+                    index: 1,
+                    status: VerifierStatus::Passed,
+                },
+                8
+            ),
+            r#"{"timestamp_ms":8,"iteration":2,"verifier":"tests","type":"verifier_status_update","data":{"status":"passed"}}"#
+        );
+        assert_eq!(
+            writer.json_line_at(&RunnerMessage::Done, 9),
+            r#"{"timestamp_ms":9,"iteration":2,"type":"done"}"#
+        );
+    }
+}
@@ -1,11 +1,17 @@
 use regex::Regex;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
 use uuid::Uuid;
 
+static CHECKBOX_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\[(x| |)\] (.+)$").unwrap());
+static SECTION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^=== (.+) ===$").unwrap());
+
 #[derive(Clone, Debug)]
 pub struct FileManager {
     pub path: PathBuf,
+    /// Serializes read-modify-write updates made by concurrent verifier tasks.
+    lock: Arc<Mutex<()>>,
 }
 
 impl FileManager {
@@ -23,7 +29,35 @@ impl FileManager {
         contents.push('\n');
 
         fs::write(&path, &contents)?;
-        Ok(Self { path })
+        Ok(Self {
+            path,
+            lock: Arc::default(),
+        })
+    }
+
+    /// Use an existing shared file, e.g. to resume the run that created it.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Arc::default(),
+        }
+    }
+
+    /// Copy the file to a private scratch file so one agent can edit it without racing
+    /// the others. Results are brought back with `record_result`.
+    pub fn fork(&self) -> std::io::Result<Self> {
+        let path = PathBuf::from(format!("/tmp/{}.txt", Uuid::new_v4()));
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::copy(&self.path, &path)?;
+        Ok(Self {
+            path,
+            lock: Arc::default(),
+        })
+    }
+
+    /// Delete the file (best effort). Used for forks once their results are recorded.
+    pub fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
     }
 
     /// Read the full file contents.
@@ -31,23 +65,59 @@ impl FileManager {
         fs::read_to_string(&self.path)
     }
 
+    /// Read the file between concurrent verifiers' updates, so the copy is never
+    /// half-written.
+    pub fn snapshot(&self) -> std::io::Result<String> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.read_contents()
+    }
+
     /// Parse checkbox states. Returns vec of (name, checked).
     pub fn parse_checkboxes(&self) -> std::io::Result<Vec<(String, bool)>> {
         let contents = self.read_contents()?;
-        let re = Regex::new(r"^\[(x| |)\] (.+)$").unwrap();
-        let mut results = Vec::new();
-        for line in contents.lines() {
-            if let Some(caps) = re.captures(line) {
-                let checked = &caps[1] == "x";
-                let name = caps[2].to_string();
-                results.push((name, checked));
+        Ok(contents.lines().filter_map(parse_checkbox_line).collect())
+    }
+
+    /// Whether the checkbox for `name` is checked.
+    pub fn is_checked(&self, name: &str) -> std::io::Result<bool> {
+        Ok(self
+            .parse_checkboxes()?
+            .iter()
+            .any(|(n, checked)| n == name && *checked))
+    }
+
+    /// The `=== name ===` sections added to this file since it contained `before`.
+    /// Used to pick a verifier's feedback out of its fork.
+    pub fn new_sections(&self, before: &str, name: &str) -> std::io::Result<Vec<String>> {
+        let after = self.read_contents()?;
+        Ok(sections(&after, name)
+            .into_iter()
+            .skip(sections(before, name).len())
+            .collect())
+    }
+
+    /// Record a verifier's result: set its checkbox and append its feedback sections.
+    pub fn record_result(&self, name: &str, passed: bool, feedback: &[String]) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut contents = set_checkbox_line(&self.read_contents()?, name, passed);
+        for section in feedback {
+            if !contents.ends_with('\n') {
+                contents.push('\n');
             }
+            contents.push_str(section);
         }
-        Ok(results)
+        fs::write(&self.path, contents)
+    }
+
+    /// Overwrite the whole file, e.g. to revert an agent's edits.
+    pub fn write_contents(&self, contents: &str) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::write(&self.path, contents)
     }
 
     /// Uncheck all checkboxes in the file.
     pub fn uncheck_all(&self) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
         let contents = self.read_contents()?;
         let re = Regex::new(r"^\[x\] ").unwrap();
         let new_contents: String = contents
@@ -70,6 +140,15 @@ impl FileManager {
         Ok(())
     }
 
+    /// Uncheck the checkboxes of the named verifiers, leaving the others as they are.
+    pub fn uncheck(&self, names: &[&str]) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let contents = names
+            .iter()
+            .fold(self.read_contents()?, |contents, name| set_checkbox_line(&contents, name, false));
+        fs::write(&self.path, contents)
+    }
+
     /// Check if all verifiers passed (all checkboxes checked).
     #[allow(dead_code)]
     pub fn all_passed(&self) -> std::io::Result<bool> {
@@ -77,3 +156,112 @@ impl FileManager {
         Ok(!checkboxes.is_empty() && checkboxes.iter().all(|(_, checked)| *checked))
     }
 }
+
+/// Parse a `[x] name` / `[] name` line into (name, checked).
+pub fn parse_checkbox_line(line: &str) -> Option<(String, bool)> {
+    CHECKBOX_RE
+        .captures(line)
+        .map(|caps| (caps[2].to_string(), &caps[1] == "x"))
+}
+
+/// The name in a `=== name ===` section header line.
+pub fn section_header(line: &str) -> Option<String> {
+    SECTION_RE.captures(line).map(|caps| caps[1].to_string())
+}
+
+/// Rewrite the checkbox line for `name`, leaving everything else untouched.
+fn set_checkbox_line(contents: &str, name: &str, checked: bool) -> String {
+    let mut out = String::with_capacity(contents.len());
+    for line in contents.split_inclusive('\n') {
+        let body = line.trim_end_matches('\n');
+        match parse_checkbox_line(body) {
+            Some((checkbox, _)) if checkbox == name => {
+                out.push_str(if checked { "[x] " } else { "[] " });
+                out.push_str(name);
+                out.push_str(&line[body.len()..]);
+            }
+            _ => out.push_str(line),
+        }
+    }
+    out
+}
+
+/// Every `=== name ===` section in the file, each from its header line up to the
+/// next `=== ... ===` header or the end of the file.
+pub fn sections(contents: &str, name: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut current: Option<String> = None;
+    for line in contents.split_inclusive('\n') {
+        if let Some(header) = section_header(line.trim_end_matches('\n')) {
+            found.extend(current.take());
+            if header == name {
+                current = Some(String::new());
+            }
+        }
+        if let Some(section) = &mut current {
+            section.push_str(line);
+        }
+    }
+    found.extend(current);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn create_writes_checkboxes_then_prompt() {
+        let fm = FileManager::create(&names(&["lint", "tests"]), "build it").unwrap();
+        assert_eq!(fm.read_contents().unwrap(), "[] lint\n[] tests\n\nbuild it\n");
+        let _ = fs::remove_file(&fm.path);
+    }
+
+    #[test]
+    fn parse_and_uncheck_checkboxes() {
+        let fm = FileManager::create(&names(&["lint", "tests"]), "build it").unwrap();
+        fs::write(&fm.path, "[x] lint\n[ ] tests\n\nbuild it\n").unwrap();
+        assert_eq!(
+            fm.parse_checkboxes().unwrap(),
+            vec![("lint".to_string(), true), ("tests".to_string(), false)]
+        );
+        assert!(!fm.all_passed().unwrap());
+
+        fm.uncheck_all().unwrap();
+        assert_eq!(fm.read_contents().unwrap(), "[] lint\n[ ] tests\n\nbuild it\n");
+        let _ = fs::remove_file(&fm.path);
+    }
+
+    #[test]
+    fn verifier_results_come_back_from_fork() {
+        let fm = FileManager::create(&names(&["lint", "tests"]), "build it").unwrap();
+        fs::write(&fm.path, "[] lint\n[] tests\n\nbuild it\n=== lint ===\nold\n").unwrap();
+        let fork = fm.fork().unwrap();
+        let before = fork.read_contents().unwrap();
+
+        // Meanwhile another verifier reports into the shared file.
+        fs::write(&fm.path, "[] lint\n[x] tests\n\nbuild it\n=== lint ===\nold\n").unwrap();
+        fs::write(
+            &fork.path,
+            "[] lint\n[x] tests\n\nbuild it\n=== lint ===\nold\n=== lint ===\nnew\n=== tests ===\nforged\n",
+        )
+        .unwrap();
+
+        assert!(!fork.is_checked("lint").unwrap());
+        assert!(fork.is_checked("tests").unwrap());
+        let feedback = fork.new_sections(&before, "lint").unwrap();
+        assert_eq!(feedback, vec!["=== lint ===\nnew\n".to_string()]);
+
+        fm.record_result("lint", false, &feedback).unwrap();
+        assert_eq!(
+            fm.read_contents().unwrap(),
+            "[] lint\n[x] tests\n\nbuild it\n=== lint ===\nold\n=== lint ===\nnew\n"
+        );
+        fork.remove();
+        let _ = fs::remove_file(&fm.path);
+    }
+}
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// How a run keeps the worker's changes in git.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitMode {
+    /// Leave the repository alone.
+    #[default]
+    Off,
+    /// Check out a new branch for the run and commit the worker's changes every iteration.
+    Branch,
+    /// Like `Branch`, but in a fresh worktree of its own, so the checkout is left alone
+    /// and several runs can share a repository.
+    Worktree,
+}
+
+impl GitMode {
+    pub const ALL: [GitMode; 3] = [GitMode::Off, GitMode::Branch, GitMode::Worktree];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GitMode::Off => "off",
+            GitMode::Branch => "branch",
+            GitMode::Worktree => "worktree",
+        }
+    }
+}
+
+/// A commit the run made, and which verifiers passed on it.
+#[derive(Clone, Debug, PartialEq)]
+struct Checkpoint {
+    iteration: u32,
+    commit: String,
+    passed: Vec<String>,
+}
+
+/// The branch a run commits to, and the commits it can go back to.
+#[derive(Clone, Debug)]
+pub struct GitRun {
+    /// Where the agents run: the working directory, or its counterpart in the worktree.
+    pub dir: PathBuf,
+    pub branch: String,
+    /// The worktree made for the run, in `Worktree` mode.
+    pub worktree: Option<PathBuf>,
+    /// HEAD when the run started.
+    base: String,
+    /// Each finished iteration's commit, oldest first.
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl GitRun {
+    /// Start a `verifiers/<id>` branch from HEAD in `dir`'s repository.
+    ///
+    /// In `Branch` mode it is checked out in place, and the tree must be clean so a reset
+    /// can never throw away the user's own edits. In `Worktree` mode it gets a new
+    /// worktree in the temp directory and the checkout isn't touched. A resumed run
+    /// carries on with `resume_branch` in `dir`, which for a worktree run is the worktree
+    /// itself: it is checked out again if the tree is clean, and the run can reset no
+    /// further back than where it resumed.
+    pub async fn start(dir: &Path, mode: GitMode, resume_branch: Option<&str>) -> Result<Self, String> {
+        let top = git(dir, &["rev-parse", "--show-toplevel"])
+            .await
+            .map_err(|_| format!("{} is not inside a git repository", dir.display()))?;
+        let base = git(dir, &["rev-parse", "HEAD"])
+            .await
+            .map_err(|_| format!("The repository at {} has no commits yet", dir.display()))?;
+        let mut run = Self {
+            dir: dir.to_path_buf(),
+            branch: String::new(),
+            worktree: None,
+            base,
+            checkpoints: Vec::new(),
+        };
+        if let Some(branch) = resume_branch {
+            let current = git(dir, &["branch", "--show-current"]).await?;
+            if current != branch {
+                if !git(dir, &["status", "--porcelain"]).await?.is_empty() {
+                    return Err(format!(
+                        "The repository at {} is on {} with uncommitted changes, not on the run's branch {}; \
+                         commit or stash them first",
+                        dir.display(),
+                        if current.is_empty() { "a detached HEAD" } else { &current },
+                        branch
+                    ));
+                }
+                git(dir, &["switch", "-q", branch])
+                    .await
+                    .map_err(|e| format!("Failed to check out the run's branch {}: {}", branch, e))?;
+                run.base = git(dir, &["rev-parse", "HEAD"]).await?;
+            }
+            run.branch = branch.to_string();
+            run.worktree = (mode == GitMode::Worktree).then(|| PathBuf::from(top));
+            return Ok(run);
+        }
+
+        let id = Uuid::new_v4().simple().to_string()[..8].to_string();
+        run.branch = format!("verifiers/{}", id);
+        match mode {
+            GitMode::Off => {}
+            GitMode::Branch => {
+                if !git(dir, &["status", "--porcelain"]).await?.is_empty() {
+                    return Err(format!(
+                        "The repository at {} has uncommitted changes; commit or stash them first",
+                        dir.display()
+                    ));
+                }
+                git(dir, &["switch", "-q", "-c", &run.branch]).await?;
+            }
+            GitMode::Worktree => {
+                let worktree = std::env::temp_dir().join(format!("verifiers_worktree_{}", id));
+                let path = worktree.display().to_string();
+                git(dir, &["worktree", "add", "-q", "-b", &run.branch, &path, "HEAD"]).await?;
+                // Run in the same subdirectory of the worktree as `dir` is of the checkout.
+                let top = Path::new(&top).canonicalize().map_err(|e| e.to_string())?;
+                let dir = dir.canonicalize().map_err(|e| e.to_string())?;
+                run.dir = match dir.strip_prefix(&top) {
+                    Ok(sub) if !sub.as_os_str().is_empty() => worktree.join(sub),
+                    _ => worktree.clone(),
+                };
+                // It is missing from the worktree if nothing in it is committed yet.
+                std::fs::create_dir_all(&run.dir)
+                    .map_err(|e| format!("Failed to create {}: {}", run.dir.display(), e))?;
+                run.worktree = Some(worktree);
+            }
+        }
+        Ok(run)
+    }
+
+    /// Commit everything the worker changed. Returns the new commit, or `None` if the
+    /// worker changed nothing.
+    pub async fn commit(&self, iteration: u32) -> Result<Option<String>, String> {
+        git(&self.dir, &["add", "-A"]).await?;
+        if git(&self.dir, &["diff", "--cached", "--quiet"]).await.is_ok() {
+            return Ok(None);
+        }
+        let message = format!("verifiers: iteration {}", iteration);
+        git(&self.dir, &["commit", "-q", "-m", &message]).await?;
+        git(&self.dir, &["rev-parse", "HEAD"]).await.map(Some)
+    }
+
+    /// Remember which verifiers passed on the current commit after `iteration`.
+    pub async fn checkpoint(&mut self, iteration: u32, passed: Vec<String>) -> Result<(), String> {
+        let commit = git(&self.dir, &["rev-parse", "HEAD"]).await?;
+        self.checkpoints.push(Checkpoint {
+            iteration,
+            commit,
+            passed,
+        });
+        Ok(())
+    }
+
+    /// The last commit on which every verifier in `required` passed, and its iteration.
+    /// The run's starting point (with no iteration) if there is none.
+    pub fn last_good(&self, required: &[String]) -> (&str, Option<u32>) {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| required.iter().all(|name| checkpoint.passed.contains(name)))
+            .map_or((self.base.as_str(), None), |checkpoint| {
+                (checkpoint.commit.as_str(), Some(checkpoint.iteration))
+            })
+    }
+
+    /// Throw away every change since `commit`, committed or not.
+    pub async fn reset_to(&self, commit: &str) -> Result<(), String> {
+        git(&self.dir, &["reset", "-q", "--hard", commit]).await.map(|_| ())
+    }
+
+    pub async fn head(&self) -> Result<String, String> {
+        git(&self.dir, &["rev-parse", "HEAD"]).await
+    }
+}
+
+/// Merge a worktree run's branch into whatever is checked out in the main checkout,
+/// then remove the worktree and the branch. `dir` is any directory in the worktree.
+/// Returns the branch merged into.
+pub async fn merge_worktree(dir: &Path, branch: &str) -> Result<String, String> {
+    let checkout = main_checkout(dir).await?;
+    let target = git(&checkout, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+    if let Err(e) = git(&checkout, &["merge", "-q", "--no-ff", "--no-edit", branch]).await {
+        // Don't leave conflict markers in the user's checkout.
+        let _ = git(&checkout, &["merge", "--abort"]).await;
+        return Err(e);
+    }
+    remove_worktree(dir).await?;
+    git(&checkout, &["branch", "-q", "-d", branch]).await?;
+    Ok(target)
+}
+
+/// Remove a worktree run's worktree, keeping its branch. Anything not committed to
+/// the branch, like build output, goes with it.
+pub async fn remove_worktree(dir: &Path) -> Result<(), String> {
+    let checkout = main_checkout(dir).await?;
+    let worktree = git(dir, &["rev-parse", "--show-toplevel"]).await?;
+    git(&checkout, &["worktree", "remove", "--force", &worktree]).await.map(|_| ())
+}
+
+/// The repository's main checkout, from any directory in one of its worktrees.
+async fn main_checkout(dir: &Path) -> Result<PathBuf, String> {
+    let common_dir = git(dir, &["rev-parse", "--path-format=absolute", "--git-common-dir"]).await?;
+    Path::new(&common_dir)
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| format!("No main checkout for {}", dir.display()))
+}
+
+/// The first 7 characters of a commit hash, for logs.
+pub fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+/// Run git in `dir` and return its trimmed stdout, or its stderr as the error.
+async fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// A repository in a temp directory with one commit, for tests.
+#[cfg(test)]
+pub async fn temp_repo() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("verifiers_git_{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for args in [
+        &["init", "-q", "-b", "main"][..],
+        &["config", "user.name", "Test"],
+        &["config", "user.email", "test@example.com"],
+    ] {
+        git(&dir, args).await.unwrap();
+    }
+    std::fs::write(dir.join("src.txt"), "initial\n").unwrap();
+    git(&dir, &["add", "-A"]).await.unwrap();
+    git(&dir, &["commit", "-q", "-m", "initial"]).await.unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn commits_on_a_branch_and_resets_to_the_last_good_commit() {
+        let dir = temp_repo().await;
+        std::fs::write(dir.join("src.txt"), "uncommitted\n").unwrap();
+        assert!(GitRun::start(&dir, GitMode::Branch, None).await.unwrap_err().contains("uncommitted changes"));
+        git(&dir, &["checkout", "-q", "--", "."]).await.unwrap();
+
+        let mut run = GitRun::start(&dir, GitMode::Branch, None).await.unwrap();
+        assert!(run.branch.starts_with("verifiers/"));
+        assert_eq!(git(&dir, &["branch", "--show-current"]).await.unwrap(), run.branch);
+        let base = run.head().await.unwrap();
+
+        std::fs::write(dir.join("src.txt"), "good\n").unwrap();
+        let good = run.commit(1).await.unwrap().unwrap();
+        run.checkpoint(1, vec!["tests".to_string()]).await.unwrap();
+        // Nothing changed, so nothing to commit.
+        assert_eq!(run.commit(2).await.unwrap(), None);
+        run.checkpoint(2, Vec::new()).await.unwrap();
+        std::fs::write(dir.join("src.txt"), "bad\n").unwrap();
+        run.commit(3).await.unwrap().unwrap();
+        run.checkpoint(3, vec!["lint".to_string()]).await.unwrap();
+
+        assert_eq!(run.last_good(&["tests".to_string()]), (good.as_str(), Some(1)));
+        assert_eq!(
+            run.last_good(&["tests".to_string(), "lint".to_string()]),
+            (base.as_str(), None)
+        );
+        run.reset_to(&good).await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("src.txt")).unwrap(), "good\n");
+        assert_eq!(
+            git(&dir, &["log", "--format=%s"]).await.unwrap(),
+            "verifiers: iteration 1\ninitial"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn resuming_goes_back_to_the_run_branch() {
+        let dir = temp_repo().await;
+        let run = GitRun::start(&dir, GitMode::Branch, None).await.unwrap();
+        std::fs::write(dir.join("src.txt"), "good\n").unwrap();
+        run.commit(1).await.unwrap().unwrap();
+        let tip = run.head().await.unwrap();
+        git(&dir, &["switch", "-q", "main"]).await.unwrap();
+
+        // Uncommitted changes on another branch would be carried over, so refuse.
+        std::fs::write(dir.join("src.txt"), "mine\n").unwrap();
+        let err = GitRun::start(&dir, GitMode::Branch, Some(&run.branch)).await.unwrap_err();
+        assert!(err.contains("is on main with uncommitted changes"), "{}", err);
+        git(&dir, &["checkout", "-q", "--", "."]).await.unwrap();
+
+        let resumed = GitRun::start(&dir, GitMode::Branch, Some(&run.branch)).await.unwrap();
+        assert_eq!(git(&dir, &["branch", "--show-current"]).await.unwrap(), run.branch);
+        assert_eq!(resumed.branch, run.branch);
+        assert_eq!(resumed.last_good(&["tests".to_string()]), (tip.as_str(), None));
+        std::fs::write(dir.join("src.txt"), "more\n").unwrap();
+        resumed.commit(2).await.unwrap().unwrap();
+        assert_eq!(git(&dir, &["log", "-1", "--format=%s", "main"]).await.unwrap(), "initial");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn worktree_runs_leave_the_checkout_alone_and_merge_back() {
+        let dir = temp_repo().await;
+        std::fs::create_dir_all(dir.join("app")).unwrap();
+        std::fs::write(dir.join("app/notes.txt"), "mine\n").unwrap();
+
+        // An uncommitted file in the checkout is fine: the worktree doesn't see it.
+        let run = GitRun::start(&dir.join("app"), GitMode::Worktree, None).await.unwrap();
+        let worktree = run.worktree.clone().unwrap();
+        assert_eq!(run.dir, worktree.join("app"));
+        assert!(!worktree.join("app/notes.txt").exists());
+        assert_eq!(git(&dir, &["branch", "--show-current"]).await.unwrap(), "main");
+
+        std::fs::write(worktree.join("src.txt"), "done\n").unwrap();
+        run.commit(1).await.unwrap().unwrap();
+        // A resumed run picks the worktree back up from its directory.
+        let resumed = GitRun::start(&worktree, GitMode::Worktree, Some(&run.branch)).await.unwrap();
+        assert_eq!((resumed.branch.as_str(), resumed.worktree), (run.branch.as_str(), Some(worktree.clone())));
+
+        assert_eq!(merge_worktree(&worktree, &run.branch).await.unwrap(), "main");
+        assert_eq!(std::fs::read_to_string(dir.join("src.txt")).unwrap(), "done\n");
+        assert_eq!(std::fs::read_to_string(dir.join("app/notes.txt")).unwrap(), "mine\n");
+        assert!(!worktree.exists());
+        assert!(git(&dir, &["rev-parse", "--verify", &run.branch]).await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn refuses_directories_outside_a_repository() {
+        let dir = std::env::temp_dir().join(format!("verifiers_nogit_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(GitRun::start(&dir, GitMode::Branch, None).await.unwrap_err().contains("not inside a git repository"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::app::{load_merged_config, parse_config, Config, RunnerMessage, DEFAULT_SUITE};
+use crate::cli::{ResumeArgs, RunArgs};
+use crate::events::EventWriter;
+use crate::file_manager::FileManager;
+use crate::git;
+use crate::history::{History, RunRecord};
+use crate::project::{self, ProjectConfig, TrustStore};
+use crate::runner::{self, RunOptions, RunOutcome};
+use tokio::sync::mpsc;
+
+/// Run the loop without the TUI, streaming progress to stderr. Returns the exit code.
+pub async fn run(args: RunArgs) -> i32 {
+    match run_inner(args).await {
+        Ok(outcome) => outcome.exit_code(),
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            RunOutcome::Failed(e).exit_code()
+        }
+    }
+}
+
+/// Continue an interrupted run from the history. Returns the exit code.
+pub async fn resume(args: ResumeArgs) -> i32 {
+    match resume_inner(args).await {
+        Ok(outcome) => outcome.exit_code(),
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            RunOutcome::Failed(e).exit_code()
+        }
+    }
+}
+
+async fn resume_inner(args: ResumeArgs) -> Result<RunOutcome, String> {
+    let history = History::open();
+    let mut record = history.load(&args.run_id)?;
+    let (fm, options) = record.resume()?;
+    let iteration = options.resume.as_ref().map_or(1, |r| r.iteration);
+    eprintln!("Resuming run {} at iteration {}", record.id, iteration);
+    eprintln!("File: {}", fm.path.display());
+    eprintln!("Working directory: {}", options.working_dir.display());
+    let mut logs = record.logs.clone();
+    logs.push(format!("Resuming run {} at iteration {}", record.id, iteration));
+    drive(&history, record, fm, options, logs, args.json).await
+}
+
+async fn run_inner(args: RunArgs) -> Result<RunOutcome, String> {
+    let prompt = std::fs::read_to_string(&args.prompt_file)
+        .map_err(|e| format!("Failed to read {}: {}", args.prompt_file.display(), e))?;
+    if prompt.trim().is_empty() {
+        return Err(format!("Prompt file {} is empty", args.prompt_file.display()));
+    }
+    let cwd = match &args.cwd {
+        Some(cwd) => cwd.clone(),
+        None => std::env::current_dir().map_err(|e| format!("Failed to read current directory: {}", e))?,
+    };
+    let working_dir = cwd
+        .canonicalize()
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| format!("Working directory {} is not a directory", cwd.display()))?;
+    // An explicit --verifiers file is used as-is; otherwise the project's
+    // .verifiers.toml is merged over the global config.
+    let mut config = match &args.verifiers_file {
+        Some(path) => load_config_file(path)?,
+        None => {
+            let project = project::discover(&working_dir)?;
+            if let Some((path, project)) = &project {
+                check_trust(path, project, args.trust_project)?;
+                eprintln!("Using project config {}", path.display());
+            }
+            load_merged_config(project.as_ref().map(|(_, project)| project))
+        }
+    };
+    config.options.working_dir = working_dir;
+    let suite = args.suite.as_deref().unwrap_or(DEFAULT_SUITE);
+    if let Some(limits) = config.suite_limits.get(suite) {
+        limits.apply(&mut config.options);
+    }
+    if let Some(concurrency) = args.concurrency {
+        config.options.concurrency = concurrency;
+    }
+    if let Some(max_iterations) = args.max_iterations {
+        config.options.max_iterations = max_iterations;
+    }
+    if let Some(secs) = args.time_budget_secs {
+        config.options.time_budget_secs = secs;
+    }
+    if let Some(secs) = args.worker_timeout_secs {
+        config.options.worker_timeout_secs = secs;
+    }
+    if let Some(secs) = args.verifier_timeout_secs {
+        config.options.verifier_timeout_secs = secs;
+    }
+    if let Some(git) = args.git {
+        config.options.git = git;
+    }
+    if let Some(names) = args.git_reset_on {
+        config.options.git_reset_on = names;
+    }
+    if let Some(retry) = args.retry {
+        config.options.retry = retry;
+    }
+    if args.fail_fast {
+        config.options.fail_fast = true;
+    }
+    let verifiers: Vec<_> = config
+        .suite(suite)
+        .ok_or_else(|| format!("Unknown suite '{}' (available: {})", suite, config.suite_names().join(", ")))?
+        .iter()
+        .filter(|v| v.enabled)
+        .cloned()
+        .collect();
+    if verifiers.is_empty() {
+        return Err("No enabled verifiers configured".to_string());
+    }
+
+    let verifier_names: Vec<String> = verifiers.iter().map(|v| v.name.clone()).collect();
+    let fm = FileManager::create(&verifier_names, &prompt)
+        .map_err(|e| format!("Failed to create shared file: {}", e))?;
+    eprintln!("File created: {}", fm.path.display());
+    eprintln!("Working directory: {}", config.options.working_dir.display());
+
+    let history = History::open();
+    let record = RunRecord::new(&prompt, suite, verifiers, &config.backend, &config.options, &fm.path);
+    drive(&history, record, fm, config.options, Vec::new(), args.json).await
+}
+
+/// Run the loop for `record`, streaming progress and saving the record to the history
+/// after every step, so the run can be resumed if it is cut short.
+async fn drive(
+    history: &History,
+    mut record: RunRecord,
+    fm: FileManager,
+    options: RunOptions,
+    mut logs: Vec<String>,
+    json: bool,
+) -> Result<RunOutcome, String> {
+    let _ = history.save(&record);
+    let file = fm.clone();
+    let verifier_names: Vec<String> = record.verifiers.iter().map(|v| v.name.clone()).collect();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let backend = record.backend.build();
+    let handle = tokio::spawn(runner::run_loop(
+        fm,
+        record.prompt.clone(),
+        record.verifiers.clone(),
+        backend,
+        options,
+        tx,
+    ));
+
+    let mut events = json.then(|| EventWriter::new(verifier_names.clone()));
+    let mut worktree = None;
+    loop {
+        tokio::select! {
+            msg = rx.recv() => {
+                let Some(msg) = msg else { break };
+                if let Some(writer) = &mut events {
+                    println!("{}", writer.json_line(&msg));
+                }
+                let changed = record.observe(&msg);
+                if let RunnerMessage::Worktree { branch, dir } = &msg {
+                    worktree = Some((branch.clone(), dir.clone()));
+                }
+                if let Some(line) = describe(&msg, &verifier_names) {
+                    eprintln!("{}", line);
+                    logs.push(line);
+                }
+                if changed {
+                    let _ = history.save_progress(&mut record, &logs);
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                // Agents run in their own process groups and don't see the SIGINT;
+                // aborting the runner drops their calls, which kills them.
+                handle.abort();
+                let _ = handle.await;
+                eprintln!("Interrupted.");
+                if let Some((_, dir)) = &worktree {
+                    eprintln!("Kept the worktree {} so the run can be resumed.", dir.display());
+                }
+                save_record(history, record, &file, logs);
+                return Ok(RunOutcome::Cancelled);
+            }
+        }
+    }
+
+    let outcome = handle.await.map_err(|e| format!("Runner task failed: {}", e));
+    // There's no one to ask, so a finished worktree run always leaves its branch.
+    if let Some((branch, dir)) = &worktree {
+        match git::remove_worktree(dir).await {
+            Ok(()) => eprintln!("The changes are on branch {}; merge them with `git merge {}`", branch, branch),
+            Err(e) => eprintln!("ERROR: Failed to remove the worktree {}: {}", dir.display(), e),
+        }
+    }
+    save_record(history, record, &file, logs);
+    outcome
+}
+
+/// Finish the run's record and add it to the history. A finished run's file is kept
+/// there, so the temp file goes, as in the TUI; an interrupted run keeps it to resume.
+fn save_record(history: &History, mut record: RunRecord, file: &FileManager, logs: Vec<String>) {
+    record.finish(file.read_contents().unwrap_or_default(), logs);
+    match history.save(&record) {
+        Ok(()) => {
+            eprintln!("Saved to history as run {}", record.id);
+            if !record.can_resume() {
+                file.remove();
+            }
+        }
+        Err(e) => eprintln!("ERROR: {}", e),
+    }
+}
+
+/// Refuse a project config that runs commands unless it has been trusted, or
+/// `--trust-project` trusts it now.
+fn check_trust(path: &std::path::Path, project: &ProjectConfig, trust_now: bool) -> Result<(), String> {
+    let commands = project.commands();
+    let store = TrustStore::open();
+    if commands.is_empty() || store.is_trusted(path, project) {
+        return Ok(());
+    }
+    if !trust_now {
+        return Err(format!(
+            "{} runs commands ({}); pass --trust-project to trust it",
+            path.display(),
+            commands.join("; ")
+        ));
+    }
+    store.trust(path, project)?;
+    eprintln!("Trusted project config {}", path.display());
+    Ok(())
+}
+
+fn load_config_file(path: &std::path::Path) -> Result<Config, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_config(&contents).map_err(|e| format!("Invalid verifiers file {}: {}", path.display(), e))
+}
+
+/// Render a runner message as a plain log line, or `None` if it has nothing to show.
+fn describe(msg: &RunnerMessage, verifier_names: &[String]) -> Option<String> {
+    match msg {
+        RunnerMessage::Log(text) => Some(text.clone()),
+        RunnerMessage::VerifierStatusUpdate { index, status } => {
+            let name = verifier_names.get(*index).map(String::as_str).unwrap_or("?");
+            Some(format!("[{}] {}", name, status.label()))
+        }
+        // The runner already logs "--- Iteration N ---" and each verdict's reasons, and file
+        // contents aren't streamed.
+        RunnerMessage::IterationStart(_)
+        | RunnerMessage::FileUpdated
+        | RunnerMessage::VerifierFeedback { .. }
+        | RunnerMessage::Snapshot { .. }
+        | RunnerMessage::GitBranch(_)
+        | RunnerMessage::Worktree { .. } => None,
+        RunnerMessage::TimedOut { agent, seconds } => {
+            Some(format!("TIMEOUT: {} killed after {}s", agent, seconds))
+        }
+        RunnerMessage::Violation { agent, details } => {
+            Some(format!("VIOLATION by {}: {} (reverted)", agent, details))
+        }
+        RunnerMessage::Done => Some("All verifiers passed!".to_string()),
+        RunnerMessage::Failed(reason) => Some(format!("FAILED: {}", reason)),
+        RunnerMessage::Aborted(reason) => Some(format!("ABORTED: {}", reason)),
+        RunnerMessage::Error(e) => Some(format!("ERROR: {}", e)),
+    }
+}
@@ -0,0 +1,505 @@
+use crate::app::{RunnerMessage, Verifier, VerifierStatus};
+use crate::backend::BackendConfig;
+use crate::file_manager::FileManager;
+use crate::runner::{ResumePoint, RunOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Runs kept on disk; older ones are deleted as new ones are saved.
+const MAX_RUNS: usize = 100;
+
+/// How a recorded run ended.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Still going, or the process died before it could record the end.
+    Running,
+    Passed,
+    Failed,
+    Aborted,
+    /// Stopped by the user before it finished.
+    Interrupted,
+}
+
+impl Outcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Outcome::Running => "running",
+            Outcome::Passed => "passed",
+            Outcome::Failed => "failed",
+            Outcome::Aborted => "aborted",
+            Outcome::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// One worker/verifier round of a recorded run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IterationRecord {
+    pub iteration: u32,
+    pub started_at_ms: u64,
+    pub finished_at_ms: Option<u64>,
+    /// The worker finished its turn, so a resumed run goes straight to the verifiers.
+    #[serde(default)]
+    pub worker_done: bool,
+    /// Each verifier's final status this iteration, parallel to `RunRecord::verifiers`.
+    pub verdicts: Vec<VerifierStatus>,
+}
+
+/// Everything needed to look back at a run or launch it again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub started_at_ms: u64,
+    pub finished_at_ms: Option<u64>,
+    pub prompt: String,
+    pub working_dir: PathBuf,
+    pub suite: String,
+    /// The verifiers that ran, in order.
+    pub verifiers: Vec<Verifier>,
+    #[serde(default)]
+    pub backend: BackendConfig,
+    pub options: RunOptions,
+    /// The shared file the run works in, kept so an interrupted run can be resumed.
+    #[serde(default)]
+    pub file_path: PathBuf,
+    /// The git branch the run commits to, if any, so a resumed run commits there too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Where the agents ran in the git worktree made for the run, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<PathBuf>,
+    pub outcome: Outcome,
+    /// Why the run failed or was aborted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub iterations: Vec<IterationRecord>,
+    pub logs: Vec<String>,
+    /// The shared file as the run left it.
+    pub final_file: String,
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl RunRecord {
+    pub fn new(
+        prompt: &str,
+        suite: &str,
+        verifiers: Vec<Verifier>,
+        backend: &BackendConfig,
+        options: &RunOptions,
+        file_path: &Path,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().simple().to_string()[..8].to_string(),
+            started_at_ms: now_ms(),
+            finished_at_ms: None,
+            prompt: prompt.to_string(),
+            working_dir: options.working_dir.clone(),
+            suite: suite.to_string(),
+            verifiers,
+            backend: backend.clone(),
+            options: options.clone(),
+            file_path: file_path.to_path_buf(),
+            git_branch: None,
+            worktree: None,
+            outcome: Outcome::Running,
+            reason: None,
+            iterations: Vec::new(),
+            logs: Vec::new(),
+            final_file: String::new(),
+        }
+    }
+
+    /// Track iterations, verdicts and the outcome from the runner's messages. Returns
+    /// whether the record changed, so it only needs saving again when it did.
+    pub fn observe(&mut self, msg: &RunnerMessage) -> bool {
+        match msg {
+            // A resumed run starts again in the iteration it stopped in.
+            RunnerMessage::IterationStart(n) if self.iterations.last().is_some_and(|last| last.iteration == *n) => {
+                if let Some(last) = self.iterations.last_mut() {
+                    last.finished_at_ms = None;
+                }
+            }
+            RunnerMessage::IterationStart(n) => {
+                let now = now_ms();
+                if let Some(last) = self.iterations.last_mut() {
+                    last.finished_at_ms.get_or_insert(now);
+                }
+                self.iterations.push(IterationRecord {
+                    iteration: *n,
+                    started_at_ms: now,
+                    finished_at_ms: None,
+                    worker_done: false,
+                    verdicts: vec![VerifierStatus::Pending; self.verifiers.len()],
+                });
+            }
+            // The worker's turn ends with a file update, before any verifier starts.
+            RunnerMessage::FileUpdated => {
+                if let Some(last) = self.iterations.last_mut() {
+                    last.worker_done = true;
+                }
+            }
+            RunnerMessage::VerifierStatusUpdate { index, status }
+                if !matches!(status, VerifierStatus::Pending | VerifierStatus::Running) =>
+            {
+                if let Some(verdict) = self.iterations.last_mut().and_then(|it| it.verdicts.get_mut(*index)) {
+                    *verdict = status.clone();
+                }
+            }
+            RunnerMessage::GitBranch(branch) => self.git_branch = Some(branch.clone()),
+            RunnerMessage::Worktree { dir, .. } => self.worktree = Some(dir.clone()),
+            RunnerMessage::Done => self.outcome = Outcome::Passed,
+            RunnerMessage::Failed(reason) => {
+                self.outcome = Outcome::Failed;
+                self.reason = Some(reason.clone());
+            }
+            RunnerMessage::Aborted(reason) => {
+                self.outcome = Outcome::Aborted;
+                self.reason = Some(reason.clone());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Close the record with the run's final file and logs. A run that never reported
+    /// an outcome was interrupted.
+    pub fn finish(&mut self, final_file: String, logs: Vec<String>) {
+        let now = now_ms();
+        if let Some(last) = self.iterations.last_mut() {
+            last.finished_at_ms.get_or_insert(now);
+        }
+        self.finished_at_ms = Some(now);
+        if self.outcome == Outcome::Running {
+            self.outcome = Outcome::Interrupted;
+        }
+        self.final_file = final_file;
+        self.logs = logs;
+    }
+
+    /// Whether the run stopped before reaching a verdict and can be picked up again.
+    pub fn can_resume(&self) -> bool {
+        matches!(self.outcome, Outcome::Running | Outcome::Interrupted)
+    }
+
+    /// The step to continue from: the last iteration the run started, with the worker's
+    /// turn and any verdicts it already finished.
+    pub fn resume_point(&self) -> ResumePoint {
+        match self.iterations.last() {
+            Some(last) => ResumePoint {
+                iteration: last.iteration,
+                worker_done: last.worker_done,
+                verdicts: last.verdicts.clone(),
+                git_branch: self.git_branch.clone(),
+            },
+            None => ResumePoint {
+                iteration: 1,
+                git_branch: self.git_branch.clone(),
+                ..ResumePoint::default()
+            },
+        }
+    }
+
+    /// Reopen an interrupted run: restore its shared file if it has gone (from the copy
+    /// saved at the end) and return the file and options to continue `run_loop` with.
+    pub fn resume(&mut self) -> Result<(FileManager, RunOptions), String> {
+        if !self.can_resume() {
+            return Err(format!("Run {} already {}; start it again instead", self.id, self.outcome.label()));
+        }
+        if !self.working_dir.is_dir() {
+            return Err(format!("Working directory {} no longer exists", self.working_dir.display()));
+        }
+        if self.file_path.as_os_str().is_empty() {
+            return Err(format!("Run {} was recorded without its shared file", self.id));
+        }
+        if !self.file_path.exists() {
+            if self.final_file.is_empty() {
+                return Err(format!("Shared file {} no longer exists", self.file_path.display()));
+            }
+            std::fs::write(&self.file_path, &self.final_file)
+                .map_err(|e| format!("Failed to restore {}: {}", self.file_path.display(), e))?;
+        }
+        // A worktree run carries on in its worktree.
+        let working_dir = match &self.worktree {
+            Some(dir) if !dir.is_dir() => return Err(format!("The run's worktree {} is gone", dir.display())),
+            Some(dir) => dir.clone(),
+            None => self.working_dir.clone(),
+        };
+        let options = RunOptions {
+            working_dir,
+            resume: Some(self.resume_point()),
+            ..self.options.clone()
+        };
+        self.outcome = Outcome::Running;
+        self.finished_at_ms = None;
+        self.reason = None;
+        Ok((FileManager::open(&self.file_path), options))
+    }
+
+    /// First line of the prompt, for lists.
+    pub fn title(&self) -> &str {
+        self.prompt.lines().find(|line| !line.trim().is_empty()).unwrap_or("").trim()
+    }
+}
+
+/// Past runs, one JSON file each under the data directory.
+pub struct History {
+    dir: PathBuf,
+}
+
+impl History {
+    pub fn open() -> Self {
+        let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        Self::at(data_dir.join("verifiers").join("runs"))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Save a run in progress, with the logs so far, after `RunRecord::observe` reported
+    /// a change. The TUI and headless runs both save through here, so a run cut short
+    /// in either resumes from the same point with its logs.
+    pub fn save_progress(&self, record: &mut RunRecord, logs: &[String]) -> Result<(), String> {
+        // Logs only grow during a run, so only the new lines are copied.
+        if let Some(new) = logs.get(record.logs.len()..) {
+            record.logs.extend_from_slice(new);
+        }
+        self.save(record)
+    }
+
+    /// Write the record, replacing an earlier save of the same run, and drop the
+    /// oldest runs beyond the limit.
+    pub fn save(&self, record: &RunRecord) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+        let json = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+        let path = self.path(&record.id);
+        let is_new = !path.exists();
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        // Runs in progress are saved often; only a new run can push the count over.
+        if is_new {
+            for old in self.list().iter().skip(MAX_RUNS) {
+                self.delete(&old.id);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<RunRecord, String> {
+        let path = self.path(id);
+        if !path.exists() {
+            return Err(format!("No run '{}' in {}", id, self.dir.display()));
+        }
+        read_record(&path)
+    }
+
+    /// Every readable run, newest first.
+    pub fn list(&self) -> Vec<RunRecord> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut runs: Vec<RunRecord> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| read_record(&entry.path()).ok())
+            .collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.started_at_ms));
+        runs
+    }
+
+    pub fn delete(&self, id: &str) {
+        let _ = std::fs::remove_file(self.path(id));
+    }
+}
+
+fn read_record(path: &Path) -> Result<RunRecord, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid run record {}: {}", path.display(), e))
+}
+
+/// A short "how long ago" for a timestamp, e.g. "5m ago".
+pub fn age_label(then_ms: u64, now_ms: u64) -> String {
+    let secs = now_ms.saturating_sub(then_ms) / 1000;
+    match secs {
+        0..60 => format!("{}s ago", secs),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// A duration in milliseconds as e.g. "2m 05s".
+pub fn duration_label(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::VerifierKind;
+
+    fn verifier(name: &str) -> Verifier {
+        Verifier {
+            name: name.to_string(),
+            prompt: "p".to_string(),
+            enabled: true,
+            kind: VerifierKind::Agent,
+            files: Vec::new(),
+            gate: false,
+        }
+    }
+
+    fn new_record(prompt: &str, suite: &str, verifiers: Vec<Verifier>) -> RunRecord {
+        let options = RunOptions::default();
+        RunRecord::new(prompt, suite, verifiers, &BackendConfig::default(), &options, Path::new("/tmp/run.txt"))
+    }
+
+    #[test]
+    fn records_iterations_and_outcome() {
+        let mut record = new_record("task", "default", vec![verifier("lint"), verifier("tests")]);
+        record.observe(&RunnerMessage::IterationStart(1));
+        record.observe(&RunnerMessage::VerifierStatusUpdate {
+            index: 1,
+            status: VerifierStatus::Failed,
+        });
+        record.observe(&RunnerMessage::IterationStart(2));
+        record.observe(&RunnerMessage::VerifierStatusUpdate {
+            index: 0,
+            status: VerifierStatus::Running,
+        });
+        record.observe(&RunnerMessage::Failed("Max iterations (2) reached".to_string()));
+        record.finish("[] lint\n".to_string(), vec!["--- Iteration 1 ---".to_string()]);
+
+        assert_eq!(record.iterations.len(), 2);
+        assert_eq!(record.iterations[0].verdicts, vec![VerifierStatus::Pending, VerifierStatus::Failed]);
+        assert!(record.iterations[0].finished_at_ms.is_some());
+        assert_eq!(record.iterations[1].verdicts[0], VerifierStatus::Pending);
+        assert_eq!(record.outcome, Outcome::Failed);
+        assert_eq!(record.reason.as_deref(), Some("Max iterations (2) reached"));
+        assert_eq!(record.final_file, "[] lint\n");
+
+        // A run that never reported an outcome was interrupted.
+        let mut record = new_record("task", "default", vec![verifier("lint")]);
+        record.finish(String::new(), Vec::new());
+        assert_eq!(record.outcome, Outcome::Interrupted);
+    }
+
+    #[test]
+    fn resumes_from_the_last_completed_step() {
+        let path = std::env::temp_dir().join(format!("verifiers_resume_{}.txt", Uuid::new_v4()));
+        let options = RunOptions {
+            working_dir: std::env::temp_dir(),
+            ..RunOptions::default()
+        };
+        let verifiers = vec![verifier("lint"), verifier("tests")];
+        let mut record = RunRecord::new("task", "default", verifiers, &BackendConfig::default(), &options, &path);
+        assert_eq!(record.resume_point().iteration, 1);
+
+        assert!(record.observe(&RunnerMessage::GitBranch("verifiers/1a2b3c4d".to_string())));
+        assert!(record.observe(&RunnerMessage::IterationStart(1)));
+        assert!(!record.observe(&RunnerMessage::Log("Starting worker...".to_string())));
+        assert!(record.observe(&RunnerMessage::FileUpdated));
+        assert!(!record.observe(&RunnerMessage::VerifierStatusUpdate {
+            index: 0,
+            status: VerifierStatus::Running,
+        }));
+        assert!(record.observe(&RunnerMessage::VerifierStatusUpdate {
+            index: 0,
+            status: VerifierStatus::Passed,
+        }));
+        record.finish("[x] lint\n[] tests\n".to_string(), Vec::new());
+
+        // The shared file is gone, so it is restored from the copy in the record.
+        let (file, options) = record.resume().unwrap();
+        assert_eq!(file.read_contents().unwrap(), "[x] lint\n[] tests\n");
+        assert_eq!(
+            options.resume,
+            Some(ResumePoint {
+                iteration: 1,
+                worker_done: true,
+                verdicts: vec![VerifierStatus::Passed, VerifierStatus::Pending],
+                git_branch: Some("verifiers/1a2b3c4d".to_string()),
+            })
+        );
+        assert_eq!(record.outcome, Outcome::Running);
+        record.observe(&RunnerMessage::IterationStart(1));
+        assert_eq!(record.iterations.len(), 1);
+        assert_eq!(record.iterations[0].verdicts[0], VerifierStatus::Passed);
+        file.remove();
+
+        // Finished runs are relaunched, not resumed.
+        record.observe(&RunnerMessage::Done);
+        assert!(record.resume().is_err());
+    }
+
+    #[test]
+    fn saves_lists_newest_first_and_deletes() {
+        let dir = std::env::temp_dir().join(format!("verifiers_history_{}", Uuid::new_v4()));
+        let history = History::at(&dir);
+        assert!(history.list().is_empty());
+
+        let mut older = new_record("first", "default", vec![verifier("lint")]);
+        older.started_at_ms -= 1000;
+        let newer = new_record("second\nmore", "docs", vec![verifier("links")]);
+        history.save(&older).unwrap();
+        history.save(&newer).unwrap();
+
+        let runs = history.list();
+        assert_eq!(runs.iter().map(RunRecord::title).collect::<Vec<_>>(), vec!["second", "first"]);
+        assert_eq!(runs[1].verifiers[0].name, "lint");
+
+        assert_eq!(history.load(&older.id).unwrap().prompt, "first");
+        history.delete(&newer.id);
+        assert_eq!(history.list()[0].id, older.id);
+        assert!(history.load(&newer.id).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn saves_progress_with_the_logs_so_far() {
+        let dir = std::env::temp_dir().join(format!("verifiers_history_{}", Uuid::new_v4()));
+        let history = History::at(&dir);
+        let mut record = new_record("task", "default", vec![verifier("lint")]);
+        let mut logs = vec!["=== Iteration 1 ===".to_string()];
+        assert!(record.observe(&RunnerMessage::IterationStart(1)));
+        history.save_progress(&mut record, &logs).unwrap();
+        logs.push("Worker done".to_string());
+        assert!(record.observe(&RunnerMessage::FileUpdated));
+        history.save_progress(&mut record, &logs).unwrap();
+
+        let saved = history.load(&record.id).unwrap();
+        assert_eq!(saved.logs, logs);
+        assert!(saved.can_resume());
+        assert!(saved.resume_point().worker_done);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn labels_ages_and_durations() {
+        assert_eq!(age_label(0, 59_000), "59s ago");
+        assert_eq!(age_label(0, 7_200_000), "2h ago");
+        assert_eq!(duration_label(125_000), "2m 05s");
+        assert_eq!(duration_label(3_660_000), "1h 01m");
+    }
+}
@@ -1,35 +1,67 @@
 mod app;
+mod assertions;
+mod backend;
+mod cli;
+mod diff;
+mod editor;
+mod events;
 mod file_manager;
+mod git;
+mod headless;
+mod history;
+mod project;
+mod retry;
 mod runner;
+mod tamper;
+mod text_area;
 mod ui;
+mod verdict;
 
-use app::{App, Screen, ScrollFocus, SetupFocus, add_to_prompt_history, load_prompt_history, load_verifiers, save_prompt_history, save_verifiers};
+use app::{App, Screen, ScrollFocus, SetupFocus, SetupOption, add_to_prompt_history, load_config, load_prompt_history, save_prompt_history, save_setup, save_verifiers, delete_suite, DEFAULT_SUITE};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use file_manager::FileManager;
+use history::History;
+use project::TrustStore;
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (cwd, suite) = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Tui { cwd, suite }) => (cwd, suite),
+        Ok(cli::Command::Run(args)) => std::process::exit(headless::run(args).await),
+        Ok(cli::Command::Resume(args)) => std::process::exit(headless::resume(args).await),
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_app(&mut terminal).await;
+    let result = run_app(&mut terminal, cwd, suite).await;
 
     // Restore terminal
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
+        DisableBracketedPaste,
         LeaveAlternateScreen
     )?;
     terminal.show_cursor()?;
@@ -41,28 +73,202 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Finish with a run's worktree: merge its branch into the main checkout, or remove the
+/// worktree and leave the branch for review. A failed merge keeps the worktree.
+async fn close_worktree(app: &mut App, merge: bool) {
+    let Some((branch, dir)) = app.worktree.take() else {
+        return;
+    };
+    let result = if merge {
+        git::merge_worktree(&dir, &branch)
+            .await
+            .map(|target| format!("Merged {} into {}", branch, target))
+    } else {
+        git::remove_worktree(&dir)
+            .await
+            .map(|()| format!("Left branch {} for review and removed its worktree", branch))
+    };
+    match result {
+        Ok(line) => app.logs.push(line),
+        Err(e) => {
+            app.logs.push(format!("ERROR: {}", e));
+            if merge {
+                app.worktree = Some((branch, dir));
+            }
+        }
+    }
+}
+
+/// Hand the terminal to `$EDITOR` to edit `text`, then take it back. The outer error
+/// is a terminal failure; the inner one is the editor's.
+fn open_in_editor(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    text: &str,
+) -> io::Result<Result<String, String>> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    let result = editor::edit_text(text);
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableBracketedPaste)?;
+    terminal.clear()?;
+    Ok(result)
+}
+
 async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    cwd: Option<PathBuf>,
+    suite: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut app = App::new();
-    app.verifiers = load_verifiers();
+    let cwd = cwd.or_else(|| std::env::current_dir().ok()).unwrap_or_default();
+    let cwd = cwd.canonicalize().unwrap_or(cwd);
+    app.set_working_dir(&cwd);
+    let mut config = load_config();
+    match project::discover(&cwd) {
+        // A project that runs commands is used without them until the user trusts it.
+        Ok(Some((path, project))) if !project.commands().is_empty() && !TrustStore::open().is_trusted(&path, &project) => {
+            let safe = project.without_commands();
+            safe.apply(&mut config);
+            app.project_config = Some((path, safe));
+            app.trust_prompt = Some(project);
+            app.project_commands_ignored = true;
+        }
+        Ok(Some((path, project))) => {
+            project.apply(&mut config);
+            app.project_config = Some((path, project));
+        }
+        Ok(None) => {}
+        Err(e) => app.project_config_error = Some(e),
+    }
+    let suite = suite.or(config.suite.clone()).unwrap_or_else(|| DEFAULT_SUITE.to_string());
+    app.load_suites(&config, &suite);
+    app.backend = config.backend;
+    app.run_options = config.options;
     app.prompt_history = load_prompt_history();
     let mut rx: Option<mpsc::UnboundedReceiver<app::RunnerMessage>> = None;
+    let mut runner_task: Option<tokio::task::JoinHandle<runner::RunOutcome>> = None;
+    let history = History::open();
 
     loop {
         terminal.draw(|f| ui::draw(f, &app))?;
 
         // Drain any pending runner messages (non-blocking)
         if let Some(receiver) = &mut rx {
+            let mut changed = false;
             while let Ok(msg) = receiver.try_recv() {
-                app.handle_runner_message(msg);
+                changed |= app.handle_runner_message(msg);
+            }
+            // Keep the saved record current, so a run cut short can be resumed.
+            if changed && !app.screen.is_finished() {
+                if let Some(record) = &mut app.current_run {
+                    let _ = history.save_progress(record, &app.logs);
+                }
+            }
+        }
+
+        // Save a finished run to the history. Its file is kept there, so the temp file goes.
+        if app.screen.is_finished() {
+            if let Some(record) = app.finish_record() {
+                match history.save(&record) {
+                    Ok(()) => {
+                        app.logs.push(format!("Saved to history as run {}", record.id));
+                        if let Some(fm) = &app.file_manager {
+                            fm.remove();
+                        }
+                    }
+                    Err(e) => app.logs.push(format!("ERROR: {}", e)),
+                }
             }
         }
 
         // Poll for keyboard events with a short timeout so we can also check messages
         if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            // Bracketed paste: the whole pasted text arrives at once, so its newlines
+            // don't act as Enter.
+            if let Event::Paste(text) = &event {
+                if app.screen == Screen::Setup {
+                    if let Some(picker) = app.suite_picker.as_mut() {
+                        picker.new_name.extend(text.chars().filter(|c| !c.is_control()));
+                    } else if let Some(input) = app.focused_input_mut() {
+                        input.insert_str(text);
+                        if app.setup_focus == SetupFocus::Prompt {
+                            app.history_index = None;
+                        }
+                    }
+                }
+            }
+            if let Event::Key(key) = event {
                 match app.screen {
+                    Screen::Setup if app.trust_prompt.is_some() => match (key.code, key.modifiers) {
+                        (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        (KeyCode::Char('y'), _) => {
+                            if let Some((path, project)) = app.trust_project(load_config()) {
+                                if let Err(e) = TrustStore::open().trust(&path, &project) {
+                                    app.project_config_error = Some(e);
+                                }
+                            }
+                        }
+                        (KeyCode::Char('n') | KeyCode::Esc, _) => {
+                            app.trust_prompt = None;
+                        }
+                        _ => {}
+                    },
+                    Screen::Setup if app.suite_picker.is_some() => {
+                        let names = app.suite_names();
+                        let project = app.project_config.as_ref().map(|(_, project)| project.clone());
+                        let picker = app.suite_picker.as_mut().expect("checked by the guard");
+                        match (key.code, key.modifiers) {
+                            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                                app.should_quit = true;
+                            }
+                            (KeyCode::Esc, _) => {
+                                app.suite_picker = None;
+                            }
+                            (KeyCode::Up, _) => {
+                                picker.selected = picker.selected.saturating_sub(1);
+                            }
+                            (KeyCode::Down, _) => {
+                                picker.selected = (picker.selected + 1).min(names.len() - 1);
+                            }
+                            // Enter: switch to the selected suite, or create the one typed
+                            (KeyCode::Enter, _) => {
+                                let new_name = picker.new_name.trim().to_string();
+                                let name = if new_name.is_empty() {
+                                    names[picker.selected].clone()
+                                } else {
+                                    new_name
+                                };
+                                let previous = app.suite.clone();
+                                app.switch_suite(&name);
+                                if name != previous {
+                                    save_verifiers(&previous, &app.suites[&previous], project.as_ref());
+                                    save_verifiers(&name, &app.verifiers, project.as_ref());
+                                }
+                            }
+                            (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                                let selected = picker.selected;
+                                if app.remove_suite(&names[selected]) {
+                                    delete_suite(&names[selected], project.as_ref());
+                                    if let Some(picker) = app.suite_picker.as_mut() {
+                                        picker.selected = selected.min(names.len() - 2);
+                                    }
+                                }
+                            }
+                            (KeyCode::Backspace, _) => {
+                                picker.new_name.pop();
+                            }
+                            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                                picker.new_name.push(c);
+                            }
+                            _ => {}
+                        }
+                    }
                     Screen::Setup => {
                         match (key.code, key.modifiers) {
                             // Quit
@@ -75,79 +281,144 @@ async fn run_app(
                             // Tab to cycle focus
                             (KeyCode::Tab, _) => {
                                 app.setup_focus = match app.setup_focus {
-                                    SetupFocus::Prompt => SetupFocus::VerifierName,
+                                    SetupFocus::Prompt => SetupFocus::WorkingDir,
+                                    SetupFocus::WorkingDir => SetupFocus::VerifierName,
                                     SetupFocus::VerifierName => SetupFocus::VerifierPrompt,
                                     SetupFocus::VerifierPrompt => {
                                         if !app.verifiers.is_empty() {
                                             SetupFocus::VerifierList
                                         } else {
-                                            SetupFocus::Prompt
+                                            SetupFocus::Options
                                         }
                                     }
-                                    SetupFocus::VerifierList => SetupFocus::Prompt,
+                                    SetupFocus::VerifierList => SetupFocus::Options,
+                                    SetupFocus::Options => SetupFocus::Prompt,
                                 };
                             }
                             (KeyCode::BackTab, _) => {
                                 app.setup_focus = match app.setup_focus {
-                                    SetupFocus::Prompt => {
+                                    SetupFocus::Prompt => SetupFocus::Options,
+                                    SetupFocus::WorkingDir => SetupFocus::Prompt,
+                                    SetupFocus::VerifierName => SetupFocus::WorkingDir,
+                                    SetupFocus::VerifierPrompt => SetupFocus::VerifierName,
+                                    SetupFocus::VerifierList => SetupFocus::VerifierPrompt,
+                                    SetupFocus::Options => {
                                         if !app.verifiers.is_empty() {
                                             SetupFocus::VerifierList
                                         } else {
                                             SetupFocus::VerifierPrompt
                                         }
                                     }
-                                    SetupFocus::VerifierName => SetupFocus::Prompt,
-                                    SetupFocus::VerifierPrompt => SetupFocus::VerifierName,
-                                    SetupFocus::VerifierList => SetupFocus::VerifierPrompt,
                                 };
                             }
-                            // Enter: add verifier (when on verifier prompt field)
-                            (KeyCode::Enter, _) if app.setup_focus == SetupFocus::VerifierPrompt => {
+                            // Ctrl+G: edit the focused prompt in $EDITOR
+                            (KeyCode::Char('g'), KeyModifiers::CONTROL) => {
+                                if let Some(text) = app.editable_text().map(str::to_string) {
+                                    match open_in_editor(terminal, &text)? {
+                                        Ok(edited) => {
+                                            app.set_edited_text(edited);
+                                            app.editor_error = None;
+                                        }
+                                        Err(e) => app.editor_error = Some(e),
+                                    }
+                                }
+                            }
+                            // Ctrl+R: browse past runs
+                            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                                app.open_history(history.list());
+                            }
+                            // Ctrl+O: open the suite picker
+                            (KeyCode::Char('o'), KeyModifiers::CONTROL) => {
+                                app.open_suite_picker();
+                            }
+                            // Ctrl+T: cycle the new verifier's kind (agent, command, assertion)
+                            (KeyCode::Char('t'), KeyModifiers::CONTROL)
+                                if matches!(app.setup_focus, SetupFocus::VerifierName | SetupFocus::VerifierPrompt) =>
+                            {
+                                app.verifier_kind_input = app.verifier_kind_input.next();
+                            }
+                            // Enter: add verifier, or save the one being edited (when on verifier prompt field).
+                            // Shift/Alt+Enter add a newline instead.
+                            (KeyCode::Enter, KeyModifiers::NONE) if app.setup_focus == SetupFocus::VerifierPrompt => {
                                 app.add_verifier();
                             }
                             // Ctrl+S: start
-                            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                                if app.can_start() {
-                                    save_verifiers(&app.verifiers);
-                                    add_to_prompt_history(&mut app.prompt_history, &app.prompt_input);
-                                    save_prompt_history(&app.prompt_history);
-                                    // Create the shared file
-                                    let verifier_names: Vec<String> =
-                                        app.verifiers.iter().filter(|v| v.enabled).map(|v| v.name.clone()).collect();
-                                    let fm = FileManager::create(&verifier_names, &app.prompt_input)?;
-                                    let file_path = fm.path.display().to_string();
-                                    app.start_running(fm.clone());
-                                    app.file_contents = fm.read_contents().unwrap_or_default();
-                                    app.logs.push(format!("File created: {}", file_path));
+                            (KeyCode::Char('s'), KeyModifiers::CONTROL) if app.can_start() => {
+                                let project = app.project_config.as_ref().map(|(_, project)| project);
+                                save_verifiers(&app.suite, &app.verifiers, project);
+                                save_setup(&app.suite, &app.run_options, &app.suite_limits, project);
+                                add_to_prompt_history(&mut app.prompt_history, app.prompt_input.text());
+                                save_prompt_history(&app.prompt_history);
+                                // Create the shared file
+                                let verifier_names: Vec<String> =
+                                    app.verifiers.iter().filter(|v| v.enabled).map(|v| v.name.clone()).collect();
+                                let fm = FileManager::create(&verifier_names, app.prompt_input.text())?;
+                                let file_path = fm.path.display().to_string();
+                                let verifiers: Vec<_> = app.verifiers.iter().filter(|v| v.enabled).cloned().collect();
+                                app.start_running(fm.clone(), &verifiers);
+                                app.file_contents = fm.read_contents().unwrap_or_default();
+                                app.logs.push(format!("File created: {}", file_path));
 
-                                    // Spawn the runner task
-                                    let (sender, receiver) = mpsc::unbounded_channel();
-                                    rx = Some(receiver);
-                                    let prompt = app.prompt_input.clone();
-                                    let verifiers: Vec<_> = app.verifiers.iter().filter(|v| v.enabled).cloned().collect();
-                                    tokio::spawn(async move {
-                                        runner::run_loop(fm, prompt, verifiers, sender).await;
-                                    });
-                                }
+                                // Spawn the runner task
+                                let (sender, receiver) = mpsc::unbounded_channel();
+                                rx = Some(receiver);
+                                let prompt = app.prompt_input.text().to_string();
+                                let backend = app.backend.build();
+                                app.run_options.working_dir = app.working_dir().expect("checked by can_start");
+                                let options = app.suite_options();
+                                let _ = history.save(app.start_record(verifiers.clone()));
+                                runner_task = Some(tokio::spawn(async move {
+                                    runner::run_loop(fm, prompt, verifiers, backend, options, sender).await
+                                }));
+                            }
+                            // Esc: stop editing a verifier without saving
+                            (KeyCode::Esc, _) if app.editing_verifier.is_some() => {
+                                app.cancel_edit();
+                            }
+                            // VerifierList: Enter to edit, Shift+Up/Down to reorder
+                            (KeyCode::Enter, _) if app.setup_focus == SetupFocus::VerifierList => {
+                                app.edit_selected_verifier();
+                            }
+                            (KeyCode::Up, KeyModifiers::SHIFT) if app.setup_focus == SetupFocus::VerifierList => {
+                                app.move_selected_verifier(-1);
+                            }
+                            (KeyCode::Down, KeyModifiers::SHIFT) if app.setup_focus == SetupFocus::VerifierList => {
+                                app.move_selected_verifier(1);
                             }
                             // VerifierList: Up/Down to navigate, Ctrl+D/Backspace to remove
                             (KeyCode::Up, _) if app.setup_focus == SetupFocus::VerifierList => {
                                 app.selected_verifier = app.selected_verifier.saturating_sub(1);
                             }
-                            (KeyCode::Down, _) if app.setup_focus == SetupFocus::VerifierList => {
-                                if !app.verifiers.is_empty() {
-                                    app.selected_verifier = (app.selected_verifier + 1).min(app.verifiers.len() - 1);
-                                }
+                            (KeyCode::Down, _)
+                                if app.setup_focus == SetupFocus::VerifierList && !app.verifiers.is_empty() =>
+                            {
+                                app.selected_verifier = (app.selected_verifier + 1).min(app.verifiers.len() - 1);
                             }
                             (KeyCode::Char(' '), _) if app.setup_focus == SetupFocus::VerifierList => {
                                 app.toggle_selected_verifier();
                             }
+                            (KeyCode::Char('g'), KeyModifiers::NONE) if app.setup_focus == SetupFocus::VerifierList => {
+                                app.toggle_selected_gate();
+                            }
                             (KeyCode::Char('d'), KeyModifiers::CONTROL) if app.setup_focus == SetupFocus::VerifierList => {
                                 app.remove_selected_verifier();
                                 if app.verifiers.is_empty() {
                                     app.setup_focus = SetupFocus::VerifierPrompt;
                                 }
                             }
+                            // Options: Up/Down to select a row, Left/Right to change it
+                            (KeyCode::Up, _) if app.setup_focus == SetupFocus::Options => {
+                                app.selected_option = app.selected_option.saturating_sub(1);
+                            }
+                            (KeyCode::Down, _) if app.setup_focus == SetupFocus::Options => {
+                                app.selected_option = (app.selected_option + 1).min(SetupOption::ALL.len() - 1);
+                            }
+                            (KeyCode::Left, _) if app.setup_focus == SetupFocus::Options => {
+                                app.adjust_selected_option(-1);
+                            }
+                            (KeyCode::Right, _) if app.setup_focus == SetupFocus::Options => {
+                                app.adjust_selected_option(1);
+                            }
                             // Ctrl+P: previous prompt in history
                             (KeyCode::Char('p'), KeyModifiers::CONTROL)
                                 if app.setup_focus == SetupFocus::Prompt
@@ -156,15 +427,14 @@ async fn run_app(
                                 let new_index = match app.history_index {
                                     None => {
                                         // Starting to browse: save current input as draft
-                                        app.history_draft = app.prompt_input.clone();
+                                        app.history_draft = app.prompt_input.text().to_string();
                                         app.prompt_history.len() - 1
                                     }
                                     Some(0) => 0, // Already at oldest
                                     Some(i) => i - 1,
                                 };
                                 app.history_index = Some(new_index);
-                                app.prompt_input = app.prompt_history[new_index].clone();
-                                app.prompt_cursor = app.prompt_input.len();
+                                app.prompt_input.set_text(app.prompt_history[new_index].clone());
                             }
                             // Ctrl+N: next prompt in history
                             (KeyCode::Char('n'), KeyModifiers::CONTROL)
@@ -174,128 +444,121 @@ async fn run_app(
                                 let i = app.history_index.unwrap();
                                 if i + 1 < app.prompt_history.len() {
                                     app.history_index = Some(i + 1);
-                                    app.prompt_input = app.prompt_history[i + 1].clone();
+                                    app.prompt_input.set_text(app.prompt_history[i + 1].clone());
                                 } else {
                                     // Past newest: restore draft
                                     app.history_index = None;
-                                    app.prompt_input = app.history_draft.clone();
-                                }
-                                app.prompt_cursor = app.prompt_input.len();
-                            }
-                            // Left/Right arrows for cursor movement in text fields
-                            (KeyCode::Left, _) if app.setup_focus != SetupFocus::VerifierList => {
-                                let (text, cursor) = match app.setup_focus {
-                                    SetupFocus::Prompt => (&app.prompt_input, &mut app.prompt_cursor),
-                                    SetupFocus::VerifierName => (&app.verifier_name_input, &mut app.verifier_name_cursor),
-                                    SetupFocus::VerifierPrompt => (&app.verifier_prompt_input, &mut app.verifier_prompt_cursor),
-                                    SetupFocus::VerifierList => unreachable!(),
-                                };
-                                if *cursor > 0 {
-                                    *cursor = text[..*cursor]
-                                        .char_indices()
-                                        .last()
-                                        .map(|(i, _)| i)
-                                        .unwrap_or(0);
+                                    app.prompt_input.set_text(app.history_draft.clone());
                                 }
                             }
-                            (KeyCode::Right, _) if app.setup_focus != SetupFocus::VerifierList => {
-                                let (text, cursor) = match app.setup_focus {
-                                    SetupFocus::Prompt => (&app.prompt_input, &mut app.prompt_cursor),
-                                    SetupFocus::VerifierName => (&app.verifier_name_input, &mut app.verifier_name_cursor),
-                                    SetupFocus::VerifierPrompt => (&app.verifier_prompt_input, &mut app.verifier_prompt_cursor),
-                                    SetupFocus::VerifierList => unreachable!(),
-                                };
-                                if *cursor < text.len() {
-                                    *cursor += text[*cursor..]
-                                        .chars()
-                                        .next()
-                                        .map(|c| c.len_utf8())
-                                        .unwrap_or(0);
-                                }
+                            // Enter for newline in prompt field
+                            (KeyCode::Enter, KeyModifiers::NONE) if app.setup_focus == SetupFocus::Prompt => {
+                                app.prompt_input.insert_char('\n');
+                                app.history_index = None;
                             }
-                            // Backspace (text fields only; VerifierList handled above)
-                            (KeyCode::Backspace, _) => match app.setup_focus {
-                                SetupFocus::Prompt => {
-                                    if app.prompt_cursor > 0 {
-                                        let prev = app.prompt_input[..app.prompt_cursor]
-                                            .char_indices()
-                                            .last()
-                                            .map(|(i, _)| i)
-                                            .unwrap_or(0);
-                                        app.prompt_input.remove(prev);
-                                        app.prompt_cursor = prev;
-                                    }
-                                    app.history_index = None;
-                                }
-                                SetupFocus::VerifierName => {
-                                    if app.verifier_name_cursor > 0 {
-                                        let prev = app.verifier_name_input[..app.verifier_name_cursor]
-                                            .char_indices()
-                                            .last()
-                                            .map(|(i, _)| i)
-                                            .unwrap_or(0);
-                                        app.verifier_name_input.remove(prev);
-                                        app.verifier_name_cursor = prev;
-                                    }
-                                }
-                                SetupFocus::VerifierPrompt => {
-                                    if app.verifier_prompt_cursor > 0 {
-                                        let prev = app.verifier_prompt_input[..app.verifier_prompt_cursor]
-                                            .char_indices()
-                                            .last()
-                                            .map(|(i, _)| i)
-                                            .unwrap_or(0);
-                                        app.verifier_prompt_input.remove(prev);
-                                        app.verifier_prompt_cursor = prev;
-                                    }
-                                }
-                                SetupFocus::VerifierList => {}
-                            },
-                            // Regular character input (no-op in VerifierList)
-                            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
-                                match app.setup_focus {
-                                    SetupFocus::Prompt => {
-                                        app.prompt_input.insert(app.prompt_cursor, c);
-                                        app.prompt_cursor += c.len_utf8();
+                            // Everything else edits the focused text field
+                            _ => {
+                                let prompt_focused = app.setup_focus == SetupFocus::Prompt;
+                                if let Some(input) = app.focused_input_mut() {
+                                    if input.input(key) && prompt_focused {
                                         app.history_index = None;
                                     }
-                                    SetupFocus::VerifierName => {
-                                        app.verifier_name_input.insert(app.verifier_name_cursor, c);
-                                        app.verifier_name_cursor += c.len_utf8();
-                                    }
-                                    SetupFocus::VerifierPrompt => {
-                                        app.verifier_prompt_input.insert(app.verifier_prompt_cursor, c);
-                                        app.verifier_prompt_cursor += c.len_utf8();
-                                    }
-                                    SetupFocus::VerifierList => {}
                                 }
                             }
-                            // Enter for newline in prompt field
-                            (KeyCode::Enter, _) if app.setup_focus == SetupFocus::Prompt => {
-                                app.prompt_input.insert(app.prompt_cursor, '\n');
-                                app.prompt_cursor += 1;
-                                app.history_index = None;
+                        }
+                    }
+                    Screen::History => {
+                        match (key.code, key.modifiers) {
+                            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                                app.should_quit = true;
+                            }
+                            (KeyCode::Esc | KeyCode::Char('q'), _) => {
+                                app.screen = Screen::Setup;
+                            }
+                            (KeyCode::Up, _) => {
+                                app.selected_run = app.selected_run.saturating_sub(1);
+                                app.history_scroll = 0;
+                            }
+                            (KeyCode::Down, _) if !app.runs.is_empty() => {
+                                app.selected_run = (app.selected_run + 1).min(app.runs.len() - 1);
+                                app.history_scroll = 0;
+                            }
+                            (KeyCode::Tab | KeyCode::BackTab, _) => {
+                                app.history_view = match app.history_view {
+                                    ScrollFocus::Log => ScrollFocus::File,
+                                    ScrollFocus::File => ScrollFocus::Log,
+                                };
+                                app.history_scroll = 0;
+                            }
+                            (KeyCode::PageUp, _) => {
+                                app.history_scroll = app.history_scroll.saturating_sub(10);
+                            }
+                            (KeyCode::PageDown, _) => {
+                                app.history_scroll = app.history_scroll.saturating_add(10);
+                            }
+                            // Enter: load the run's setup to start it again
+                            (KeyCode::Enter, _) => {
+                                app.relaunch_selected_run();
+                            }
+                            // r: continue an interrupted run from its last completed step
+                            (KeyCode::Char('r'), KeyModifiers::NONE) => {
+                                if let Some((fm, verifiers, options)) = app.resume_selected_run() {
+                                    let (sender, receiver) = mpsc::unbounded_channel();
+                                    rx = Some(receiver);
+                                    let record = app.current_run.as_ref().expect("set by resume_selected_run");
+                                    let prompt = record.prompt.clone();
+                                    let backend = record.backend.build();
+                                    let _ = history.save(record);
+                                    runner_task = Some(tokio::spawn(async move {
+                                        runner::run_loop(fm, prompt, verifiers, backend, options, sender).await
+                                    }));
+                                }
+                            }
+                            (KeyCode::Char('d'), KeyModifiers::CONTROL) if !app.runs.is_empty() => {
+                                let run = app.runs.remove(app.selected_run);
+                                history.delete(&run.id);
+                                app.selected_run = app.selected_run.min(app.runs.len().saturating_sub(1));
+                                app.history_scroll = 0;
                             }
                             _ => {}
                         }
                     }
-                    Screen::Running | Screen::Done => {
+                    Screen::Running | Screen::Done | Screen::Failed | Screen::Aborted => {
                         match (key.code, key.modifiers) {
                             (KeyCode::Char('q'), _) => {
                                 app.should_quit = true;
                             }
                             (KeyCode::Char('n'), KeyModifiers::CONTROL)
-                                if app.screen == Screen::Done =>
+                                if app.screen.is_finished() =>
                             {
+                                close_worktree(&mut app, false).await;
                                 app.reset_for_new_run();
                                 rx = None;
                             }
                             (KeyCode::Char('e'), KeyModifiers::CONTROL)
-                                if app.screen == Screen::Done =>
+                                if app.screen.is_finished() =>
                             {
+                                close_worktree(&mut app, false).await;
                                 app.edit_and_rerun();
                                 rx = None;
                             }
+                            // m / l: merge the worktree's branch, or leave it for review
+                            (KeyCode::Char('m'), KeyModifiers::NONE)
+                                if app.screen == Screen::Done && app.worktree.is_some() =>
+                            {
+                                close_worktree(&mut app, true).await;
+                            }
+                            (KeyCode::Char('l'), KeyModifiers::NONE)
+                                if app.screen.is_finished() && app.worktree.is_some() =>
+                            {
+                                close_worktree(&mut app, false).await;
+                            }
+                            // d: swap the file panel for a diff between snapshots
+                            (KeyCode::Char('d'), KeyModifiers::NONE) => app.toggle_diff(),
+                            (KeyCode::Left, _) if app.show_diff => app.move_diff(-1, 0),
+                            (KeyCode::Right, _) if app.show_diff => app.move_diff(1, 0),
+                            (KeyCode::Char('['), _) if app.show_diff => app.move_diff(0, -1),
+                            (KeyCode::Char(']'), _) if app.show_diff => app.move_diff(0, 1),
                             (KeyCode::Tab | KeyCode::BackTab, _) => {
                                 app.scroll_focus = match app.scroll_focus {
                                     ScrollFocus::Log => ScrollFocus::File,
@@ -326,6 +589,18 @@ async fn run_app(
         }
 
         if app.should_quit {
+            // Dropping the runner's in-flight agent calls kills their processes.
+            if let Some(task) = runner_task.take() {
+                task.abort();
+                let _ = task.await;
+            }
+            if let Some(record) = app.finish_record() {
+                let _ = history.save(&record);
+            }
+            // An unfinished run keeps its worktree so it can be resumed.
+            if app.screen.is_finished() {
+                close_worktree(&mut app, false).await;
+            }
             return Ok(());
         }
     }
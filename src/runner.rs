@@ -1,47 +1,182 @@
-use crate::app::{RunnerMessage, Verifier, VerifierStatus};
+use crate::app::{RunnerMessage, Verifier, VerifierKind, VerifierStatus};
+use crate::assertions;
+use crate::backend::{self, AgentBackend, AgentRequest, AgentRole, CommandOutput};
 use crate::file_manager::FileManager;
-use std::fs;
-use tokio::process::Command;
+use crate::git::{self, GitMode, GitRun};
+use crate::retry::{self, Fingerprint, RetryPolicy};
+use crate::tamper;
+use crate::verdict::{parse_verdict, Verdict};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 use tokio::sync::mpsc;
-use uuid::Uuid;
+use tokio::task::{JoinError, JoinSet};
 
-/// Write a prompt string to a temp file and return the path.
-fn write_prompt_file(prompt: &str) -> std::io::Result<String> {
-    let path = format!("/tmp/verifiers_prompt_{}.txt", Uuid::new_v4());
-    fs::write(&path, prompt)?;
-    Ok(path)
+/// Per-run settings, persisted in `verifiers.json` alongside the verifiers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunOptions {
+    /// How many verifiers may run at the same time.
+    pub concurrency: usize,
+    /// Give up after this many iterations. 0 means no limit: keep going until every
+    /// verifier passes, the worker fails, or the run is stopped.
+    pub max_iterations: u32,
+    /// Start no new iteration once the run has taken this many seconds, counted from
+    /// when it was started or resumed. 0 means no limit.
+    pub time_budget_secs: u64,
+    /// Kill the worker if a single call runs longer than this many seconds. 0 means no limit.
+    pub worker_timeout_secs: u64,
+    /// Kill a verifier if a single call runs longer than this many seconds. 0 means no limit.
+    pub verifier_timeout_secs: u64,
+    /// Whether to commit the worker's changes to a branch of their own.
+    pub git: GitMode,
+    /// With `git` on, when any of these verifiers fail, reset the project to the last
+    /// commit on which they all passed. Empty: never reset.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub git_reset_on: Vec<String>,
+    /// Which verifiers run again after an iteration in which some failed.
+    pub retry: RetryPolicy,
+    /// End an iteration as soon as a verifier fails, skipping the ones not started yet.
+    /// If any verifier is marked `gate`, only gates end it, and the verifiers after a
+    /// gate wait for it to pass.
+    pub fail_fast: bool,
+    /// The project directory agents and commands run in. Chosen per run, so not saved.
+    #[serde(skip)]
+    pub working_dir: PathBuf,
+    /// Where to pick up an interrupted run instead of starting at iteration 1.
+    #[serde(skip)]
+    pub resume: Option<ResumePoint>,
 }
 
-/// Remove a temp prompt file (best effort).
-fn cleanup_prompt_file(path: &str) {
-    let _ = fs::remove_file(path);
+/// The last step an interrupted run completed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResumePoint {
+    /// The iteration to continue.
+    pub iteration: u32,
+    /// The worker finished this iteration, so continue with the verifiers.
+    pub worker_done: bool,
+    /// Verdicts already recorded this iteration, parallel to the verifiers. Verifiers
+    /// with a final status aren't run again.
+    pub verdicts: Vec<VerifierStatus>,
+    /// The git branch the run was committing to. `None` starts a new one.
+    pub git_branch: Option<String>,
 }
 
-/// Run `claude --dangerously-skip-permissions -p "$(cat {prompt_file})"` and return stdout.
-async fn run_claude(prompt: &str) -> Result<String, String> {
-    let prompt_file = write_prompt_file(prompt).map_err(|e| format!("Failed to write prompt file: {}", e))?;
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            max_iterations: 10,
+            time_budget_secs: 0,
+            worker_timeout_secs: 0,
+            verifier_timeout_secs: 0,
+            git: GitMode::Off,
+            git_reset_on: Vec::new(),
+            retry: RetryPolicy::All,
+            fail_fast: false,
+            working_dir: PathBuf::from("."),
+            resume: None,
+        }
+    }
+}
 
-    let result = Command::new("bash")
-        .arg("-c")
-        .arg(format!(
-            "cat '{}' | claude --dangerously-skip-permissions -p -",
-            prompt_file
-        ))
-        .output()
-        .await
-        .map_err(|e| format!("Failed to spawn claude: {}", e))?;
+impl RunOptions {
+    /// `max_iterations` for display: a number or "unlimited".
+    pub fn max_iterations_label(&self) -> String {
+        match self.max_iterations {
+            0 => "unlimited".to_string(),
+            n => n.to_string(),
+        }
+    }
+}
+
+/// A suite's own run limits, overriding the global ones for runs of that suite.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_budget_secs: Option<u64>,
+}
 
-    cleanup_prompt_file(&prompt_file);
+impl SuiteLimits {
+    /// Override `options` with every limit set here.
+    pub fn apply(&self, options: &mut RunOptions) {
+        if let Some(max_iterations) = self.max_iterations {
+            options.max_iterations = max_iterations;
+        }
+        if let Some(secs) = self.time_budget_secs {
+            options.time_budget_secs = secs;
+        }
+    }
 
-    if result.status.success() {
-        Ok(String::from_utf8_lossy(&result.stdout).to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&result.stderr);
-        let stdout = String::from_utf8_lossy(&result.stdout);
-        Err(format!(
-            "claude exited with {}: stdout={}, stderr={}",
-            result.status, stdout, stderr
-        ))
+    /// The inverse of `apply`: put `global`'s value back for every limit set here, so a
+    /// suite's limits don't end up in the global options.
+    pub fn unapply(&self, options: &mut RunOptions, global: &RunOptions) {
+        if self.max_iterations.is_some() {
+            options.max_iterations = global.max_iterations;
+        }
+        if self.time_budget_secs.is_some() {
+            options.time_budget_secs = global.time_budget_secs;
+        }
+    }
+}
+
+/// Why an agent call produced no output.
+enum CallError {
+    Failed(String),
+    TimedOut(u64),
+}
+
+/// Failed command verifiers keep only this many trailing lines of output as feedback.
+const MAX_COMMAND_OUTPUT_LINES: usize = 200;
+
+/// Run one agent call, giving up after `timeout_secs` (0 means wait forever). Dropping
+/// the backend's future on timeout kills the agent process.
+async fn call_agent(backend: &dyn AgentBackend, request: &AgentRequest, timeout_secs: u64) -> Result<String, CallError> {
+    with_timeout(backend.run(request), timeout_secs).await
+}
+
+/// Await `call`, giving up after `timeout_secs` (0 means wait forever).
+async fn with_timeout<T>(
+    call: impl Future<Output = Result<T, String>>,
+    timeout_secs: u64,
+) -> Result<T, CallError> {
+    if timeout_secs == 0 {
+        return call.await.map_err(CallError::Failed);
+    }
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), call).await {
+        Ok(result) => result.map_err(CallError::Failed),
+        Err(_) => Err(CallError::TimedOut(timeout_secs)),
+    }
+}
+
+/// How a run ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunOutcome {
+    /// Every verifier passed.
+    Passed,
+    /// The iteration limit or the time budget ran out with verifiers still failing.
+    MaxIterations,
+    /// The worker or the runner itself failed; the run cannot continue.
+    Failed(String),
+    /// The run was interrupted by the user.
+    Cancelled,
+}
+
+impl RunOutcome {
+    /// Process exit code for headless runs.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::Passed => 0,
+            RunOutcome::MaxIterations => 1,
+            RunOutcome::Failed(_) => 2,
+            RunOutcome::Cancelled => 130,
+        }
     }
 }
 
@@ -50,12 +185,60 @@ pub async fn run_loop(
     file_manager: FileManager,
     _prompt: String,
     verifiers: Vec<Verifier>,
+    backend: Arc<dyn AgentBackend>,
+    options: RunOptions,
     tx: mpsc::UnboundedSender<RunnerMessage>,
-) {
-    let file_path = file_manager.path.display().to_string();
-    let max_iterations = 10;
+) -> RunOutcome {
+    let max_iterations = options.max_iterations;
+    let started = Instant::now();
 
-    for iteration in 1..=max_iterations {
+    snapshot(&file_manager, "start", &tx);
+    let mut options = options;
+    let mut git_run = match options.git {
+        GitMode::Off => None,
+        mode => match GitRun::start(
+            &options.working_dir,
+            mode,
+            options.resume.as_ref().and_then(|r| r.git_branch.as_deref()),
+        )
+        .await
+        {
+            Ok(git_run) => {
+                let _ = tx.send(RunnerMessage::Log(format!(
+                    "Committing the worker's changes to branch {}",
+                    git_run.branch
+                )));
+                let _ = tx.send(RunnerMessage::GitBranch(git_run.branch.clone()));
+                if git_run.worktree.is_some() {
+                    let _ = tx.send(RunnerMessage::Log(format!("Working in {}", git_run.dir.display())));
+                    let _ = tx.send(RunnerMessage::Worktree {
+                        branch: git_run.branch.clone(),
+                        dir: git_run.dir.clone(),
+                    });
+                    options.working_dir = git_run.dir.clone();
+                }
+                Some(git_run)
+            }
+            Err(e) => return abort(&tx, e),
+        },
+    };
+    let mut resume = options.resume.clone();
+    let mut iteration = resume.as_ref().map_or(0, |r| r.iteration.saturating_sub(1));
+    // The last iteration's verdicts and, for the `affected` policy, the project's files
+    // as the verifiers saw them. Empty until an iteration has failed.
+    let mut last_passed: Vec<bool> = Vec::new();
+    let mut checked_files: Option<Fingerprint> = None;
+    let gates = gates(&verifiers, &options);
+    // Names that aren't in this run could never pass, so they are left out.
+    let required: Vec<String> = options
+        .git_reset_on
+        .iter()
+        .filter(|name| verifiers.iter().any(|v| &v.name == *name))
+        .cloned()
+        .collect();
+    while max_iterations == 0 || iteration < max_iterations {
+        iteration += 1;
+        let resume = resume.take().unwrap_or_default();
         let _ = tx.send(RunnerMessage::IterationStart(iteration));
         let _ = tx.send(RunnerMessage::Log(format!(
             "--- Iteration {} ---",
@@ -63,141 +246,1366 @@ pub async fn run_loop(
         )));
 
         // Step 1: Run the worker
-        let _ = tx.send(RunnerMessage::Log("Starting worker...".to_string()));
-        let worker_prompt = format!(
-            "You are a worker agent. Read the file at {} and follow the instructions in it. \
-             Do the work described by the prompt in the file. When you are done, append a section \
-             to the file in this format:\n\n=== worker ===\n<describe what you did>\n\n\
-             Important: Do NOT modify the checkbox lines at the top of the file. Only append your work section.",
-            file_path
-        );
+        if resume.worker_done {
+            let _ = tx.send(RunnerMessage::Log(
+                "Worker already finished this iteration; resuming with the verifiers.".to_string(),
+            ));
+        } else {
+            if let Err(outcome) = run_worker(iteration, &file_manager, backend.as_ref(), &options, &tx).await {
+                return outcome;
+            }
+            snapshot(&file_manager, "worker", &tx);
+            if let Some(git_run) = &git_run {
+                match git_run.commit(iteration).await {
+                    Ok(Some(commit)) => {
+                        let _ = tx.send(RunnerMessage::Log(format!(
+                            "Committed iteration {} as {}",
+                            iteration,
+                            git::short(&commit)
+                        )));
+                    }
+                    Ok(None) => {
+                        let _ = tx.send(RunnerMessage::Log("The worker changed nothing to commit.".to_string()));
+                    }
+                    Err(e) => return abort(&tx, format!("Failed to commit the worker's changes: {}", e)),
+                }
+            }
+        }
 
-        match run_claude(&worker_prompt).await {
-            Ok(_) => {
-                let _ = tx.send(RunnerMessage::Log("Worker complete.".to_string()));
+        // Step 2: Run the verifiers, up to `options.concurrency` at a time
+        let kept = kept_passes(&verifiers, &last_passed, checked_files.as_ref(), &options, &tx);
+        let rerun: Vec<&str> = verifiers
+            .iter()
+            .zip(&last_passed)
+            .zip(&kept)
+            .filter(|((_, &was_passed), &kept)| was_passed && !kept)
+            .map(|((v, _), _)| v.name.as_str())
+            .collect();
+        if let Err(e) = file_manager.uncheck(&rerun) {
+            return abort(&tx, format!("Failed to uncheck boxes: {}", e));
+        }
+        let limit = options.concurrency.max(1);
+        let mut tasks = JoinSet::new();
+        let mut passed = vec![false; verifiers.len()];
+        let mut finished = vec![false; verifiers.len()];
+        let mut failed_gate = None;
+        for (i, verifier) in verifiers.iter().enumerate() {
+            if kept[i] {
+                passed[i] = true;
+                finished[i] = true;
+                let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
+                    index: i,
+                    status: VerifierStatus::Passed,
+                });
+                continue;
+            }
+            // A verifier that finished before the run was interrupted keeps its verdict.
+            if let Some(status @ (VerifierStatus::Passed | VerifierStatus::Failed | VerifierStatus::TimedOut)) =
+                resume.verdicts.get(i)
+            {
+                passed[i] = *status == VerifierStatus::Passed;
+                finished[i] = true;
+                if gates[i] && !passed[i] {
+                    failed_gate.get_or_insert(i);
+                }
+                let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
+                    index: i,
+                    status: status.clone(),
+                });
+                continue;
+            }
+            // Wait for a free slot, and for any marked gate before this verifier to pass.
+            // Slots are freed by joining, so a failure is seen before the next one starts.
+            while failed_gate.is_none()
+                && (tasks.len() >= limit || (0..i).any(|j| gates[j] && verifiers[j].gate && !finished[j]))
+            {
+                let Some(joined) = tasks.join_next().await else { break };
+                failed_gate = finish_verifier(joined, &gates, &mut passed, &mut finished);
             }
-            Err(e) => {
-                let _ = tx.send(RunnerMessage::Error(format!("Worker failed: {}", e)));
-                return;
+            if failed_gate.is_some() {
+                break;
+            }
+            let verifier = verifier.clone();
+            let file_manager = file_manager.clone();
+            let backend = backend.clone();
+            let tx = tx.clone();
+            let options = options.clone();
+            tasks.spawn(async move {
+                let result = run_verifier(
+                    i,
+                    &verifier,
+                    iteration,
+                    &options,
+                    &file_manager,
+                    backend.as_ref(),
+                    &tx,
+                )
+                .await;
+                snapshot(&file_manager, &verifier.name, &tx);
+                (i, result)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            if let Some(gate) = finish_verifier(joined, &gates, &mut passed, &mut finished) {
+                failed_gate.get_or_insert(gate);
+            }
+        }
+        let mut skipped = Vec::new();
+        if let Some(gate) = failed_gate {
+            for (i, verifier) in verifiers.iter().enumerate().filter(|(i, _)| !finished[*i]) {
+                skipped.push(verifier.name.clone());
+                let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
+                    index: i,
+                    status: VerifierStatus::Skipped,
+                });
+            }
+            if !skipped.is_empty() {
+                let _ = tx.send(RunnerMessage::Log(format!(
+                    "{} failed; skipped {}",
+                    verifiers[gate].name,
+                    skipped.join(", ")
+                )));
+            }
+        }
+
+        // Step 3: Check results
+        if passed.iter().all(|&p| p) {
+            let _ = tx.send(RunnerMessage::FileUpdated);
+            let _ = tx.send(RunnerMessage::Done);
+            return RunOutcome::Passed;
+        }
+
+        // Not all passed — uncheck the boxes of the verifiers that will run again and retry
+        if options.retry == RetryPolicy::All {
+            let _ = tx.send(RunnerMessage::Log(
+                "Not all verifiers passed. Unchecking all boxes and retrying...".to_string(),
+            ));
+            if let Err(e) = file_manager.uncheck_all() {
+                return abort(&tx, format!("Failed to uncheck boxes: {}", e));
+            }
+        } else {
+            let _ = tx.send(RunnerMessage::Log(format!(
+                "Not all verifiers passed. Retrying (retry policy: {})...",
+                options.retry.label()
+            )));
+            if options.retry == RetryPolicy::Affected {
+                checked_files = Some(Fingerprint::take(&options.working_dir));
+            }
+            last_passed = passed.clone();
+        }
+        if let Some(git_run) = &mut git_run {
+            let passed: Vec<String> = verifiers
+                .iter()
+                .zip(&passed)
+                .filter(|(_, &p)| p)
+                .map(|(v, _)| v.name.clone())
+                .collect();
+            match reset_failed_work(git_run, iteration, &required, passed, &skipped, &file_manager, &tx).await {
+                // Passes kept for the retry were earned by the work the reset discarded.
+                Ok(true) if !last_passed.is_empty() => {
+                    last_passed.clear();
+                    checked_files = None;
+                    if let Err(e) = file_manager.uncheck_all() {
+                        return abort(&tx, format!("Failed to uncheck boxes: {}", e));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => return abort(&tx, format!("Failed to reset the project: {}", e)),
             }
         }
         let _ = tx.send(RunnerMessage::FileUpdated);
+        if options.time_budget_secs > 0 && started.elapsed() >= Duration::from_secs(options.time_budget_secs) {
+            let _ = tx.send(RunnerMessage::Failed(format!(
+                "Used up the time budget ({}s) after {} iterations. Stopping.",
+                options.time_budget_secs, iteration
+            )));
+            return RunOutcome::MaxIterations;
+        }
+    }
 
-        // Step 2: Run each verifier sequentially
-        let mut all_passed = true;
-        for (i, verifier) in verifiers.iter().enumerate() {
+    let _ = tx.send(RunnerMessage::Failed(format!(
+        "Reached maximum iterations ({}). Stopping.",
+        max_iterations
+    )));
+    RunOutcome::MaxIterations
+}
+
+/// Which verifiers end the iteration when they fail, parallel to `verifiers`: none
+/// without `fail_fast`, otherwise the ones marked `gate`, or all of them if none is.
+fn gates(verifiers: &[Verifier], options: &RunOptions) -> Vec<bool> {
+    if !options.fail_fast {
+        return vec![false; verifiers.len()];
+    }
+    let any_gate = verifiers.iter().any(|v| v.gate);
+    verifiers.iter().map(|v| v.gate || !any_gate).collect()
+}
+
+/// Record a verifier task's result. Returns its index if it was a gate and failed.
+fn finish_verifier(
+    joined: Result<(usize, bool), JoinError>,
+    gates: &[bool],
+    passed: &mut [bool],
+    finished: &mut [bool],
+) -> Option<usize> {
+    let (i, result) = joined.ok()?;
+    passed[i] = result;
+    finished[i] = true;
+    (gates[i] && !result).then_some(i)
+}
+
+/// Which verifiers keep their pass from the last iteration instead of running again,
+/// parallel to `verifiers`. Only after a failed iteration, and never with the `all`
+/// retry policy.
+fn kept_passes(
+    verifiers: &[Verifier],
+    last_passed: &[bool],
+    checked_files: Option<&Fingerprint>,
+    options: &RunOptions,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> Vec<bool> {
+    let mut kept = vec![false; verifiers.len()];
+    if last_passed.len() != verifiers.len() {
+        return kept;
+    }
+    match (options.retry, checked_files) {
+        (RetryPolicy::Failed, _) => kept.copy_from_slice(last_passed),
+        (RetryPolicy::Affected, Some(checked_files)) => {
+            let changed = Fingerprint::take(&options.working_dir).changed_since(checked_files);
+            for (i, verifier) in verifiers.iter().enumerate() {
+                let affected = if verifier.files.is_empty() {
+                    !changed.is_empty()
+                } else {
+                    retry::touches(&verifier.files, &changed)
+                };
+                kept[i] = last_passed[i] && !affected;
+            }
+        }
+        _ => return kept,
+    }
+    let names: Vec<&str> = verifiers
+        .iter()
+        .zip(&kept)
+        .filter(|(_, &kept)| kept)
+        .map(|(v, _)| v.name.as_str())
+        .collect();
+    if !names.is_empty() {
+        let _ = tx.send(RunnerMessage::Log(format!(
+            "Keeping the passes of {} from the last iteration",
+            names.join(", ")
+        )));
+    }
+    kept
+}
+
+/// Record this iteration's verdicts on the run's branch. If any of the `required`
+/// verifiers failed, go back to the last commit on which they all passed and tell the
+/// worker in the shared file. Skipped verifiers don't count as failed. Returns whether
+/// the project was reset.
+async fn reset_failed_work(
+    git_run: &mut GitRun,
+    iteration: u32,
+    required: &[String],
+    passed: Vec<String>,
+    skipped: &[String],
+    file_manager: &FileManager,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> Result<bool, String> {
+    let failed: Vec<&str> = required
+        .iter()
+        .filter(|name| !passed.contains(name) && !skipped.contains(name))
+        .map(String::as_str)
+        .collect();
+    git_run.checkpoint(iteration, passed.clone()).await?;
+    if failed.is_empty() {
+        return Ok(false);
+    }
+    let (commit, good_iteration) = git_run.last_good(required);
+    let commit = commit.to_string();
+    if commit == git_run.head().await? {
+        return Ok(false);
+    }
+    git_run.reset_to(&commit).await?;
+    let since = match good_iteration {
+        Some(n) => format!("the commit from iteration {}", n),
+        None => "where it was before this run".to_string(),
+    };
+    let _ = tx.send(RunnerMessage::Log(format!(
+        "{} failed; reset the project to {} ({})",
+        failed.join(", "),
+        git::short(&commit),
+        since
+    )));
+    let note = format!(
+        "=== git ===\n{} failed in iteration {}, so the project was reset to {}. \
+         Changes made after that were discarded.\n",
+        failed.join(", "),
+        iteration,
+        since
+    );
+    let mut contents = file_manager.read_contents().map_err(|e| e.to_string())?;
+    if !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(&note);
+    file_manager.write_contents(&contents).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Run the worker for one iteration and revert any edits it made to the checkboxes.
+/// On failure, returns the outcome to end the run with.
+async fn run_worker(
+    iteration: u32,
+    file_manager: &FileManager,
+    backend: &dyn AgentBackend,
+    options: &RunOptions,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> Result<(), RunOutcome> {
+    let _ = tx.send(RunnerMessage::Log("Starting worker...".to_string()));
+    let file_path = file_manager.path.display();
+    let worker_prompt = format!(
+        "You are a worker agent working in the project directory {}. Read the file at {} and \
+         follow the instructions in it. Do the work described by the prompt in the file. When you are done, append a section \
+         to the file in this format:\n\n=== worker ===\n<describe what you did>\n\n\
+         Important: Do NOT modify the checkbox lines at the top of the file. Only append your work section.",
+        options.working_dir.display(),
+        file_path
+    );
+
+    let before = match file_manager.read_contents() {
+        Ok(contents) => contents,
+        Err(e) => return Err(abort(tx, format!("Failed to read file: {}", e))),
+    };
+    let request = AgentRequest {
+        role: AgentRole::Worker,
+        iteration,
+        file_path: file_manager.path.clone(),
+        prompt: worker_prompt,
+        working_dir: options.working_dir.clone(),
+    };
+    match call_agent(backend, &request, options.worker_timeout_secs).await {
+        Ok(_) => {
+            let _ = tx.send(RunnerMessage::Log("Worker complete.".to_string()));
+        }
+        Err(CallError::Failed(e)) => return Err(abort(tx, format!("Worker failed: {}", e))),
+        Err(CallError::TimedOut(seconds)) => {
+            let _ = tx.send(RunnerMessage::TimedOut {
+                agent: "worker".to_string(),
+                seconds,
+            });
+            return Err(abort(tx, format!("Worker timed out after {}s", seconds)));
+        }
+    }
+    if let Err(e) = revert_worker_tampering(file_manager, &before, tx) {
+        return Err(abort(tx, format!("Failed to revert worker edits: {}", e)));
+    }
+    let _ = tx.send(RunnerMessage::FileUpdated);
+    Ok(())
+}
+
+/// Send a copy of the shared file as `label`'s step left it.
+fn snapshot(file_manager: &FileManager, label: &str, tx: &mpsc::UnboundedSender<RunnerMessage>) {
+    if let Ok(contents) = file_manager.snapshot() {
+        let _ = tx.send(RunnerMessage::Snapshot {
+            label: label.to_string(),
+            contents,
+        });
+    }
+}
+
+/// End the run early: tell the UI why and report the failure.
+fn abort(tx: &mpsc::UnboundedSender<RunnerMessage>, error: String) -> RunOutcome {
+    let _ = tx.send(RunnerMessage::Aborted(error.clone()));
+    RunOutcome::Failed(error)
+}
+
+/// Run one verifier and record its checkbox and feedback in the shared file.
+/// Returns whether it passed.
+async fn run_verifier(
+    index: usize,
+    verifier: &Verifier,
+    iteration: u32,
+    options: &RunOptions,
+    file_manager: &FileManager,
+    backend: &dyn AgentBackend,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> bool {
+    let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
+        index,
+        status: VerifierStatus::Running,
+    });
+    let _ = tx.send(RunnerMessage::Log(format!(
+        "Running verifier: {}...",
+        verifier.name
+    )));
+
+    let result = match verifier.kind {
+        VerifierKind::Agent => {
+            run_agent_verifier(verifier, iteration, options, file_manager, backend, tx).await
+        }
+        VerifierKind::Command => run_command_verifier(verifier, iteration, options, file_manager).await,
+        VerifierKind::Assert => run_assert_verifier(verifier, options, file_manager),
+    };
+    let passed = match result {
+        Ok((passed, feedback)) => {
+            if !passed && !feedback.is_empty() {
+                let _ = tx.send(RunnerMessage::VerifierFeedback {
+                    index,
+                    feedback: feedback.concat(),
+                });
+            }
+            passed
+        }
+        Err(CallError::Failed(e)) => {
+            let _ = tx.send(RunnerMessage::Error(e.clone()));
+            let _ = tx.send(RunnerMessage::VerifierFeedback { index, feedback: e });
             let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
-                index: i,
-                status: VerifierStatus::Running,
+                index,
+                status: VerifierStatus::Failed,
+            });
+            return false;
+        }
+        Err(CallError::TimedOut(seconds)) => {
+            let _ = tx.send(RunnerMessage::TimedOut {
+                agent: verifier.name.clone(),
+                seconds,
+            });
+            let _ = tx.send(RunnerMessage::VerifierFeedback {
+                index,
+                feedback: format!("Timed out after {}s", seconds),
             });
+            let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
+                index,
+                status: VerifierStatus::TimedOut,
+            });
+            return false;
+        }
+    };
+    let _ = tx.send(RunnerMessage::FileUpdated);
+
+    let (status, label) = if passed {
+        (VerifierStatus::Passed, "PASSED")
+    } else {
+        (VerifierStatus::Failed, "FAILED")
+    };
+    let _ = tx.send(RunnerMessage::VerifierStatusUpdate { index, status });
+    let _ = tx.send(RunnerMessage::Log(format!("{}: {}", verifier.name, label)));
+    passed
+}
+
+/// Run an agent verifier against its own fork of the shared file, then merge its
+/// checkbox and feedback back. Returns whether it passed and the feedback it left.
+async fn run_agent_verifier(
+    verifier: &Verifier,
+    iteration: u32,
+    options: &RunOptions,
+    file_manager: &FileManager,
+    backend: &dyn AgentBackend,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> Result<(bool, Vec<String>), CallError> {
+    let fork = file_manager.fork().map_err(|e| {
+        CallError::Failed(format!("Failed to copy file for verifier '{}': {}", verifier.name, e))
+    })?;
+    let before = fork.read_contents().unwrap_or_default();
+    let fork_path = fork.path.display().to_string();
+
+    let verifier_prompt = format!(
+        "You are a verifier agent named '{}'. Read the file at {}. The project being worked on \
+         is in {}.\n\n\
+         Your verification criteria: {}\n\n\
+         Instructions:\n\
+         1. Read the file and evaluate the worker's output against your criteria.\n\
+         2. Do NOT edit the checkbox lines at the top of the file. The runner records your verdict.\n\
+         3. If the work FAILS your verification, you may append a section to the file with details:\n\
+            === {} ===\n\
+            <explain why it failed and what needs to be fixed>\n\
+         4. End your reply with your verdict as a single JSON object on its own line, either\n\
+            {{\"verdict\": \"pass\", \"reasons\": []}}\n\
+            or\n\
+            {{\"verdict\": \"fail\", \"reasons\": [\"<what is wrong and how to fix it>\"]}}\n\n\
+         Do not touch other verifiers' checkboxes or sections.",
+        verifier.name,
+        fork_path,
+        options.working_dir.display(),
+        verifier.prompt,
+        verifier.name,
+    );
+
+    let request = AgentRequest {
+        role: AgentRole::Verifier(verifier.name.clone()),
+        iteration,
+        file_path: fork.path.clone(),
+        prompt: verifier_prompt,
+        working_dir: options.working_dir.clone(),
+    };
+    let recorded = match call_agent(backend, &request, options.verifier_timeout_secs).await {
+        Ok(stdout) => record_verdict(&stdout, verifier, file_manager, &fork, &before, tx).map_err(|e| {
+            CallError::Failed(format!("Failed to record result of verifier '{}': {}", verifier.name, e))
+        }),
+        Err(CallError::Failed(e)) => Err(CallError::Failed(format!(
+            "Verifier '{}' failed to run: {}",
+            verifier.name, e
+        ))),
+        Err(timed_out) => Err(timed_out),
+    };
+    fork.remove();
+    recorded
+}
+
+/// Run a command verifier's shell command. It passes on exit 0; otherwise its output
+/// becomes the verifier's feedback section so the worker sees it next iteration.
+async fn run_command_verifier(
+    verifier: &Verifier,
+    iteration: u32,
+    options: &RunOptions,
+    file_manager: &FileManager,
+) -> Result<(bool, Vec<String>), CallError> {
+    let request = AgentRequest {
+        role: AgentRole::Verifier(verifier.name.clone()),
+        iteration,
+        file_path: file_manager.path.clone(),
+        prompt: String::new(),
+        working_dir: options.working_dir.clone(),
+    };
+    let output = with_timeout(backend::run_command(&verifier.prompt, &request), options.verifier_timeout_secs)
+        .await
+        .map_err(|e| match e {
+            CallError::Failed(e) => CallError::Failed(format!("Verifier '{}' failed to run: {}", verifier.name, e)),
+            timed_out => timed_out,
+        })?;
+    let feedback = if output.success {
+        Vec::new()
+    } else {
+        vec![command_section(&verifier.name, &verifier.prompt, &output)]
+    };
+    file_manager
+        .record_result(&verifier.name, output.success, &feedback)
+        .map_err(|e| CallError::Failed(format!("Failed to record result of verifier '{}': {}", verifier.name, e)))?;
+    Ok((output.success, feedback))
+}
+
+/// Check an assertion verifier's file assertions. Failed assertions become the reasons
+/// in its feedback section.
+fn run_assert_verifier(
+    verifier: &Verifier,
+    options: &RunOptions,
+    file_manager: &FileManager,
+) -> Result<(bool, Vec<String>), CallError> {
+    let failures = assertions::check_all(&verifier.prompt, &options.working_dir)
+        .map_err(|e| CallError::Failed(format!("Verifier '{}' has invalid assertions: {}", verifier.name, e)))?;
+    let verdict = Verdict {
+        passed: failures.is_empty(),
+        reasons: failures,
+    };
+    let feedback = if verdict.passed {
+        Vec::new()
+    } else {
+        vec![verdict.to_section(&verifier.name)]
+    };
+    file_manager
+        .record_result(&verifier.name, verdict.passed, &feedback)
+        .map_err(|e| CallError::Failed(format!("Failed to record result of verifier '{}': {}", verifier.name, e)))?;
+    Ok((verdict.passed, feedback))
+}
+
+/// Render a failed command's output as a `=== name ===` feedback section, keeping only
+/// the last `MAX_COMMAND_OUTPUT_LINES` lines.
+fn command_section(name: &str, command: &str, output: &CommandOutput) -> String {
+    let mut section = format!("=== {} ===\n`{}` failed ({}):\n", name, command, output.status);
+    let lines: Vec<&str> = output.output.lines().collect();
+    let skipped = lines.len().saturating_sub(MAX_COMMAND_OUTPUT_LINES);
+    if skipped > 0 {
+        section.push_str(&format!("... ({} earlier lines omitted)\n", skipped));
+    }
+    for line in &lines[skipped..] {
+        section.push_str(line);
+        section.push('\n');
+    }
+    section
+}
+
+/// Undo any worker edits above its append-only region, keeping its new work sections.
+fn revert_worker_tampering(
+    file_manager: &FileManager,
+    before: &str,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> std::io::Result<()> {
+    let after = file_manager.read_contents()?;
+    if let Some(violation) = tamper::check_worker(before, &after) {
+        let _ = tx.send(RunnerMessage::Violation {
+            agent: "worker".to_string(),
+            details: violation.details,
+        });
+        file_manager.write_contents(&violation.repaired)?;
+    }
+    Ok(())
+}
+
+/// Decide pass/fail from the verifier's JSON verdict, falling back to its checkbox in
+/// the fork if it printed none, and record the result in the shared file. Only the
+/// verifier's own sections are copied over; any other edits to its fork are reported
+/// and dropped. Returns whether it passed and the feedback sections it left.
+fn record_verdict(
+    stdout: &str,
+    verifier: &Verifier,
+    file_manager: &FileManager,
+    fork: &FileManager,
+    before: &str,
+    tx: &mpsc::UnboundedSender<RunnerMessage>,
+) -> std::io::Result<(bool, Vec<String>)> {
+    if let Some(details) = tamper::check_verifier(before, &fork.read_contents()?, &verifier.name) {
+        let _ = tx.send(RunnerMessage::Violation {
+            agent: verifier.name.clone(),
+            details,
+        });
+    }
+    let mut feedback = fork.new_sections(before, &verifier.name)?;
+    let passed = match parse_verdict(stdout) {
+        Some(verdict) => {
+            if !verdict.reasons.is_empty() {
+                let _ = tx.send(RunnerMessage::Log(format!(
+                    "{}: {}",
+                    verifier.name,
+                    verdict.reasons.join("; ")
+                )));
+            }
+            if !verdict.passed && feedback.is_empty() {
+                feedback.push(verdict.to_section(&verifier.name));
+            }
+            verdict.passed
+        }
+        None => {
             let _ = tx.send(RunnerMessage::Log(format!(
-                "Running verifier: {}...",
+                "{}: no verdict in output, using its checkbox instead",
                 verifier.name
             )));
+            fork.is_checked(&verifier.name)?
+        }
+    };
+    file_manager.record_result(&verifier.name, passed, &feedback)?;
+    Ok((passed, feedback))
+}
 
-            let verifier_prompt = format!(
-                "You are a verifier agent named '{}'. Read the file at {}.\n\n\
-                 Your verification criteria: {}\n\n\
-                 Instructions:\n\
-                 1. Read the file and evaluate the worker's output against your criteria.\n\
-                 2. If the work PASSES your verification:\n\
-                    - Edit the file to change the line '[] {}' to '[x] {}'\n\
-                 3. If the work FAILS your verification:\n\
-                    - Do NOT check the checkbox (leave it as '[] {}')\n\
-                    - Append a section to the file:\n\
-                      === {} ===\n\
-                      <explain why it failed and what needs to be fixed>\n\n\
-                 Only modify YOUR checkbox line. Do not touch other verifiers' checkboxes.",
-                verifier.name,
-                file_path,
-                verifier.prompt,
-                verifier.name,
-                verifier.name,
-                verifier.name,
-                verifier.name,
-            );
-
-            match run_claude(&verifier_prompt).await {
-                Ok(_) => {}
-                Err(e) => {
-                    let _ = tx.send(RunnerMessage::Error(format!(
-                        "Verifier '{}' failed to run: {}",
-                        verifier.name, e
-                    )));
-                    let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
-                        index: i,
-                        status: VerifierStatus::Failed,
-                    });
-                    all_passed = false;
-                    continue;
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::{MockAction, MockBackend};
 
-            let _ = tx.send(RunnerMessage::FileUpdated);
+    fn verifier(name: &str) -> Verifier {
+        Verifier {
+            name: name.to_string(),
+            prompt: format!("check {}", name),
+            enabled: true,
+            kind: VerifierKind::Agent,
+            files: Vec::new(),
+            gate: false,
+        }
+    }
 
-            // Check if this verifier's checkbox is checked
-            match file_manager.parse_checkboxes() {
-                Ok(checkboxes) => {
-                    let passed = checkboxes
-                        .iter()
-                        .find(|(name, _)| name == &verifier.name)
-                        .map(|(_, checked)| *checked)
-                        .unwrap_or(false);
-
-                    if passed {
-                        let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
-                            index: i,
-                            status: VerifierStatus::Passed,
-                        });
-                        let _ = tx.send(RunnerMessage::Log(format!(
-                            "{}: PASSED",
-                            verifier.name
-                        )));
-                    } else {
-                        let _ = tx.send(RunnerMessage::VerifierStatusUpdate {
-                            index: i,
-                            status: VerifierStatus::Failed,
-                        });
-                        let _ = tx.send(RunnerMessage::Log(format!(
-                            "{}: FAILED",
-                            verifier.name
-                        )));
-                        all_passed = false;
+    /// Run the loop to completion against a mock backend and collect every message.
+    async fn run(
+        backend: MockBackend,
+        names: &[&str],
+    ) -> (RunOutcome, FileManager, Arc<MockBackend>, Vec<RunnerMessage>) {
+        let options = RunOptions {
+            concurrency: 1,
+            ..RunOptions::default()
+        };
+        run_with(backend, names, options).await
+    }
+
+    async fn run_with(
+        backend: MockBackend,
+        names: &[&str],
+        options: RunOptions,
+    ) -> (RunOutcome, FileManager, Arc<MockBackend>, Vec<RunnerMessage>) {
+        run_verifiers(backend, names.iter().map(|n| verifier(n)).collect(), options).await
+    }
+
+    async fn run_verifiers(
+        backend: MockBackend,
+        verifiers: Vec<Verifier>,
+        options: RunOptions,
+    ) -> (RunOutcome, FileManager, Arc<MockBackend>, Vec<RunnerMessage>) {
+        let verifier_names: Vec<String> = verifiers.iter().map(|v| v.name.clone()).collect();
+        let fm = FileManager::create(&verifier_names, "do the thing").unwrap();
+        let backend = Arc::new(backend);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let outcome = run_loop(fm.clone(), "do the thing".to_string(), verifiers, backend.clone(), options, tx).await;
+        let mut messages = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            messages.push(msg);
+        }
+        (outcome, fm, backend, messages)
+    }
+
+    fn iterations(messages: &[RunnerMessage]) -> Vec<u32> {
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::IterationStart(n) => Some(*n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn errors(messages: &[RunnerMessage]) -> Vec<String> {
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::Error(e) => Some(e.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn passes_on_first_iteration() {
+        let backend = MockBackend::new()
+            .worker(None, MockAction::Append("=== worker ===\ndid it\n".to_string()))
+            .verifier("a", None, MockAction::Check)
+            .verifier("b", None, MockAction::Check);
+        let (outcome, fm, backend, messages) = run(backend, &["a", "b"]).await;
+
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![1]);
+        assert!(matches!(messages.last(), Some(RunnerMessage::Done)));
+        assert!(errors(&messages).is_empty());
+        assert!(fm.all_passed().unwrap());
+        assert!(fm.read_contents().unwrap().contains("=== worker ===\ndid it\n"));
+        let snapshots: Vec<(&str, &str)> = messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::Snapshot { label, contents } => Some((label.as_str(), contents.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            snapshots.iter().map(|(label, _)| *label).collect::<Vec<_>>(),
+            vec!["start", "worker", "a", "b"]
+        );
+        assert!(!snapshots[0].1.contains("=== worker ==="));
+        assert!(snapshots[1].1.contains("=== worker ===") && snapshots[1].1.contains("[] a"));
+        assert!(snapshots[3].1.contains("[x] a\n[x] b"));
+        assert_eq!(
+            backend.calls(),
+            vec![
+                (AgentRole::Worker, 1),
+                (AgentRole::Verifier("a".to_string()), 1),
+                (AgentRole::Verifier("b".to_string()), 1),
+            ]
+        );
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_last_completed_step() {
+        let backend = MockBackend::new()
+            .verifier("a", None, MockAction::Check)
+            .verifier("b", None, MockAction::Check);
+        let options = RunOptions {
+            concurrency: 1,
+            resume: Some(ResumePoint {
+                iteration: 2,
+                worker_done: true,
+                verdicts: vec![VerifierStatus::Passed, VerifierStatus::Running],
+                git_branch: None,
+            }),
+            ..RunOptions::default()
+        };
+        let (outcome, fm, backend, messages) = run_with(backend, &["a", "b"], options).await;
+
+        // The worker and the verifier that already passed aren't run again.
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![2]);
+        assert_eq!(backend.calls(), vec![(AgentRole::Verifier("b".to_string()), 2)]);
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn commits_each_iteration_and_resets_when_a_required_verifier_fails() {
+        let dir = git::temp_repo().await;
+        // "tests" passes on the worker's first attempt and fails on its second.
+        let backend = MockBackend::new()
+            .worker(Some(1), MockAction::WriteFile("src.txt".to_string(), "good\n".to_string()))
+            .worker(Some(2), MockAction::WriteFile("src.txt".to_string(), "bad\n".to_string()))
+            .verifier("tests", Some(1), MockAction::Check);
+        let options = RunOptions {
+            concurrency: 1,
+            max_iterations: 2,
+            git: GitMode::Branch,
+            git_reset_on: vec!["tests".to_string()],
+            working_dir: dir.clone(),
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, _) = run_with(backend, &["tests", "lint"], options).await;
+
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        assert_eq!(std::fs::read_to_string(dir.join("src.txt")).unwrap(), "good\n");
+        let log = std::process::Command::new("git")
+            .args(["log", "--format=%s"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&log.stdout), "verifiers: iteration 1\ninitial\n");
+        assert!(fm
+            .read_contents()
+            .unwrap()
+            .contains("=== git ===\ntests failed in iteration 2, so the project was reset to the commit from iteration 1."));
+        let _ = std::fs::remove_file(&fm.path);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn passes_are_not_kept_across_a_reset() {
+        let dir = git::temp_repo().await;
+        let backend = MockBackend::new()
+            .worker(None, MockAction::WriteFile("src.txt".to_string(), "changed\n".to_string()))
+            .verifier("lint", None, MockAction::Check)
+            .verifier("tests", Some(2), MockAction::Check);
+        let options = RunOptions {
+            concurrency: 1,
+            max_iterations: 2,
+            git: GitMode::Branch,
+            git_reset_on: vec!["tests".to_string()],
+            retry: RetryPolicy::Failed,
+            working_dir: dir.clone(),
+            ..RunOptions::default()
+        };
+        let (outcome, fm, backend, _) = run_with(backend, &["tests", "lint"], options).await;
+
+        // "lint" passed on the work that failing "tests" had reset, so it runs again.
+        assert_eq!(outcome, RunOutcome::Passed);
+        let lint_runs = backend
+            .calls()
+            .into_iter()
+            .filter(|(role, _)| *role == AgentRole::Verifier("lint".to_string()))
+            .count();
+        assert_eq!(lint_runs, 2);
+        let _ = std::fs::remove_file(&fm.path);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn retries_until_late_verifier_passes() {
+        let backend = MockBackend::new()
+            .verifier("a", None, MockAction::Check)
+            .verifier("b", Some(1), MockAction::Append("=== b ===\nnot yet\n".to_string()))
+            .verifier("b", Some(2), MockAction::Check);
+        let (outcome, fm, _, messages) = run(backend, &["a", "b"]).await;
+
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![1, 2]);
+        assert!(matches!(messages.last(), Some(RunnerMessage::Done)));
+        let b_statuses: Vec<&VerifierStatus> = messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::VerifierStatusUpdate { index: 1, status } => Some(status),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            b_statuses,
+            vec![
+                &VerifierStatus::Running,
+                &VerifierStatus::Failed,
+                &VerifierStatus::Running,
+                &VerifierStatus::Passed,
+            ]
+        );
+        let contents = fm.read_contents().unwrap();
+        assert!(contents.starts_with("[x] a\n[x] b\n"));
+        assert!(contents.contains("=== b ===\nnot yet\n"));
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn failing_gate_skips_the_verifiers_after_it() {
+        let backend = MockBackend::new()
+            .verifier("compiles", Some(2), MockAction::Check)
+            .verifier("tests", None, MockAction::Check)
+            .verifier("review", None, MockAction::Check);
+        let verifiers = vec![
+            Verifier {
+                gate: true,
+                ..verifier("compiles")
+            },
+            verifier("tests"),
+            verifier("review"),
+        ];
+        let options = RunOptions {
+            fail_fast: true,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, backend, messages) = run_verifiers(backend, verifiers, options).await;
+
+        // The later verifiers wait for the gate even with room to run in parallel.
+        assert_eq!(outcome, RunOutcome::Passed);
+        let verifier_calls: Vec<(String, u32)> = backend
+            .calls()
+            .into_iter()
+            .filter_map(|(role, iteration)| match role {
+                AgentRole::Verifier(name) => Some((name, iteration)),
+                AgentRole::Worker => None,
+            })
+            .collect();
+        assert_eq!(
+            verifier_calls,
+            vec![
+                ("compiles".to_string(), 1),
+                ("compiles".to_string(), 2),
+                ("tests".to_string(), 2),
+                ("review".to_string(), 2),
+            ]
+        );
+        let skipped: Vec<usize> = messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::VerifierStatusUpdate {
+                    index,
+                    status: VerifierStatus::Skipped,
+                } => Some(*index),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(skipped, vec![1, 2]);
+        let _ = std::fs::remove_file(&fm.path);
+
+        // Without any gates marked, verifiers still run in parallel, and the first
+        // failure stops the ones that haven't started.
+        let backend = MockBackend::new()
+            .verifier("a", None, MockAction::Delay(Duration::from_millis(200)))
+            .verifier("a", None, MockAction::Check);
+        let options = RunOptions {
+            concurrency: 2,
+            max_iterations: 1,
+            fail_fast: true,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, backend, messages) = run_with(backend, &["a", "b", "c", "d"], options).await;
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        assert_eq!(
+            backend.calls(),
+            vec![
+                (AgentRole::Worker, 1),
+                (AgentRole::Verifier("a".to_string()), 1),
+                (AgentRole::Verifier("b".to_string()), 1),
+            ]
+        );
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            RunnerMessage::VerifierStatusUpdate { index: 0, status: VerifierStatus::Passed }
+        )));
+        assert!(messages
+            .iter()
+            .any(|m| matches!(m, RunnerMessage::Log(text) if text == "b failed; skipped c, d")));
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn retry_policies_rerun_only_failed_or_affected_verifiers() {
+        let dir = std::env::temp_dir().join(format!("verifiers_retry_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        // The worker only ever changes src/, and "late" passes on the third iteration.
+        let verifiers = vec![
+            Verifier {
+                files: vec!["docs".to_string()],
+                ..verifier("docs")
+            },
+            Verifier {
+                files: vec!["src/*.rs".to_string()],
+                ..verifier("code")
+            },
+            verifier("late"),
+        ];
+        let runs = |policy| {
+            let backend = (1..=3)
+                .fold(MockBackend::new(), |backend, n| {
+                    backend.worker(Some(n), MockAction::WriteFile("src/lib.rs".to_string(), format!("v{}", n)))
+                })
+                .verifier("docs", None, MockAction::Check)
+                .verifier("code", None, MockAction::Check)
+                .verifier("late", Some(3), MockAction::Check);
+            let options = RunOptions {
+                concurrency: 1,
+                retry: policy,
+                working_dir: dir.clone(),
+                ..RunOptions::default()
+            };
+            let verifiers = verifiers.clone();
+            async move {
+                let (outcome, fm, backend, _) = run_verifiers(backend, verifiers, options).await;
+                assert_eq!(outcome, RunOutcome::Passed);
+                assert!(fm.all_passed().unwrap());
+                let _ = std::fs::remove_file(&fm.path);
+                let mut counts = [0; 3];
+                for (role, _) in backend.calls() {
+                    match role {
+                        AgentRole::Verifier(name) if name == "docs" => counts[0] += 1,
+                        AgentRole::Verifier(name) if name == "code" => counts[1] += 1,
+                        AgentRole::Verifier(_) => counts[2] += 1,
+                        AgentRole::Worker => {}
                     }
                 }
-                Err(e) => {
-                    let _ = tx.send(RunnerMessage::Error(format!(
-                        "Failed to parse checkboxes: {}",
-                        e
-                    )));
-                    all_passed = false;
-                }
+                counts
             }
+        };
+
+        assert_eq!(runs(RetryPolicy::All).await, [3, 3, 3]);
+        assert_eq!(runs(RetryPolicy::Failed).await, [1, 1, 3]);
+        // "code" watches the file the worker keeps changing; "late" watches everything.
+        assert_eq!(runs(RetryPolicy::Affected).await, [1, 3, 3]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_iterations() {
+        let backend = MockBackend::new().verifier("a", None, MockAction::Check);
+        let (outcome, fm, _, messages) = run(backend, &["a", "b"]).await;
+
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        assert_eq!(outcome.exit_code(), 1);
+        assert_eq!(iterations(&messages), (1..=10).collect::<Vec<_>>());
+        assert!(!messages.iter().any(|m| matches!(m, RunnerMessage::Done)));
+        assert!(errors(&messages).is_empty());
+        assert!(matches!(
+            messages.last(),
+            Some(RunnerMessage::Failed(reason)) if reason == "Reached maximum iterations (10). Stopping."
+        ));
+        // Boxes are unchecked between iterations.
+        assert!(fm.read_contents().unwrap().starts_with("[] a\n[] b\n"));
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_verifiers_do_not_lose_each_others_results() {
+        let names = ["a", "b", "c", "d", "e"];
+        let mut backend = MockBackend::new();
+        for name in names {
+            backend = backend
+                .verifier(name, Some(1), MockAction::Append(format!("=== {} ===\nretry\n", name)))
+                .verifier(name, Some(2), MockAction::Check);
         }
+        let options = RunOptions {
+            concurrency: 3,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, messages) = run_with(backend, &names, options).await;
 
-        // Step 3: Check results
-        if all_passed {
-            let _ = tx.send(RunnerMessage::FileUpdated);
-            let _ = tx.send(RunnerMessage::Done);
-            return;
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![1, 2]);
+        let contents = fm.read_contents().unwrap();
+        assert!(contents.starts_with("[x] a\n[x] b\n[x] c\n[x] d\n[x] e\n"));
+        for name in names {
+            assert!(contents.contains(&format!("=== {} ===\nretry\n", name)));
         }
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn verdict_decides_result_and_writes_feedback() {
+        let pass = MockAction::Reply("Looks fine.\n{\"verdict\": \"pass\", \"reasons\": []}\n".to_string());
+        let fail = MockAction::Reply("{\"verdict\": \"fail\", \"reasons\": [\"missing docs\"]}".to_string());
+        let backend = MockBackend::new()
+            // Passes by verdict without touching its checkbox.
+            .verifier("a", None, pass.clone())
+            // Ticks its box but says it failed: the verdict wins.
+            .verifier("b", Some(1), MockAction::Check)
+            .verifier("b", Some(1), fail)
+            .verifier("b", Some(2), pass);
+        let (outcome, fm, _, messages) = run(backend, &["a", "b"]).await;
+
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![1, 2]);
+        let contents = fm.read_contents().unwrap();
+        assert!(contents.starts_with("[x] a\n[x] b\n"));
+        assert!(contents.contains("=== b ===\n- missing docs\n"));
+        assert!(messages
+            .iter()
+            .any(|m| matches!(m, RunnerMessage::Log(l) if l == "b: missing docs")));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            RunnerMessage::VerifierFeedback { index: 1, feedback } if feedback == "=== b ===\n- missing docs\n"
+        )));
+        let _ = std::fs::remove_file(&fm.path);
+    }
 
-        // Not all passed — uncheck all boxes and retry
-        let _ = tx.send(RunnerMessage::Log(
-            "Not all verifiers passed. Unchecking all boxes and retrying...".to_string(),
+    #[tokio::test]
+    async fn out_of_region_edits_are_reported_and_reverted() {
+        let backend = MockBackend::new()
+            .worker(None, MockAction::Replace("[] b\n".to_string(), "[x] b\n".to_string()))
+            .worker(None, MockAction::Append("=== worker ===\ndone\n".to_string()))
+            .verifier("a", None, MockAction::Replace("[] b\n".to_string(), "[x] b\n".to_string()))
+            .verifier("a", None, MockAction::Reply("{\"verdict\": \"pass\"}".to_string()));
+        let (outcome, fm, _, messages) = run(backend, &["a", "b"]).await;
+
+        // b never checks its own box, so nobody else's edits can make it pass.
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        let violators: Vec<&str> = messages
+            .iter()
+            .take_while(|m| !matches!(m, RunnerMessage::IterationStart(2)))
+            .filter_map(|m| match m {
+                RunnerMessage::Violation { agent, .. } => Some(agent.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(violators, vec!["worker", "a"]);
+        let contents = fm.read_contents().unwrap();
+        assert!(contents.starts_with("[] a\n[] b\n\ndo the thing\n=== worker ===\ndone\n"));
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn max_iterations_is_configurable() {
+        let backend = MockBackend::new();
+        let options = RunOptions {
+            concurrency: 1,
+            max_iterations: 3,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, messages) = run_with(backend, &["a"], options).await;
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        assert_eq!(iterations(&messages), vec![1, 2, 3]);
+        let _ = std::fs::remove_file(&fm.path);
+
+        // Unlimited keeps going until the verifiers pass.
+        let backend = MockBackend::new().verifier("a", Some(15), MockAction::Check);
+        let options = RunOptions {
+            concurrency: 1,
+            max_iterations: 0,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, messages) = run_with(backend, &["a"], options).await;
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages).len(), 15);
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    // The clock is paused, so the worker's turns take no real time.
+    #[tokio::test(start_paused = true)]
+    async fn stops_when_the_time_budget_is_used_up() {
+        let backend = MockBackend::new().worker(None, MockAction::Delay(Duration::from_secs(40)));
+        let options = RunOptions {
+            concurrency: 1,
+            max_iterations: 0,
+            time_budget_secs: 60,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, messages) = run_with(backend, &["a"], options).await;
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        assert_eq!(iterations(&messages), vec![1, 2]);
+        assert!(matches!(
+            messages.last(),
+            Some(RunnerMessage::Failed(reason)) if reason == "Used up the time budget (60s) after 2 iterations. Stopping."
         ));
-        if let Err(e) = file_manager.uncheck_all() {
-            let _ = tx.send(RunnerMessage::Error(format!(
-                "Failed to uncheck boxes: {}",
-                e
-            )));
-            return;
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[test]
+    fn suite_limits_override_only_what_they_set() {
+        let mut options = RunOptions::default();
+        SuiteLimits {
+            max_iterations: Some(0),
+            time_budget_secs: None,
         }
-        let _ = tx.send(RunnerMessage::FileUpdated);
+        .apply(&mut options);
+        assert_eq!(options.max_iterations, 0);
+        assert_eq!(options.time_budget_secs, 0);
+
+        let global = RunOptions::default();
+        SuiteLimits {
+            max_iterations: Some(3),
+            time_budget_secs: None,
+        }
+        .unapply(&mut options, &global);
+        assert_eq!(options.max_iterations, 10);
     }
 
-    let _ = tx.send(RunnerMessage::Error(format!(
-        "Reached maximum iterations ({}). Stopping.",
-        max_iterations
-    )));
+    #[tokio::test]
+    async fn hung_agents_time_out() {
+        let hang = MockAction::Delay(Duration::from_secs(60));
+        let backend = MockBackend::new()
+            .verifier("a", Some(1), hang.clone())
+            .verifier("a", Some(2), MockAction::Check)
+            .worker(Some(3), hang);
+        let options = RunOptions {
+            concurrency: 1,
+            worker_timeout_secs: 1,
+            verifier_timeout_secs: 1,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, messages) = run_with(backend, &["a", "b"], options).await;
+
+        assert_eq!(outcome, RunOutcome::Failed("Worker timed out after 1s".to_string()));
+        let timeouts: Vec<&str> = messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::TimedOut { agent, seconds: 1 } => Some(agent.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(timeouts, vec!["a", "worker"]);
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            RunnerMessage::VerifierStatusUpdate { index: 0, status: VerifierStatus::TimedOut }
+        )));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            RunnerMessage::VerifierFeedback { index: 0, feedback } if feedback == "Timed out after 1s"
+        )));
+        assert!(matches!(messages.last(), Some(RunnerMessage::Aborted(_))));
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn worker_failure_stops_the_loop() {
+        let backend = MockBackend::new().worker(None, MockAction::Fail("boom".to_string()));
+        let (outcome, fm, backend, messages) = run(backend, &["a"]).await;
+
+        assert_eq!(outcome, RunOutcome::Failed("Worker failed: boom".to_string()));
+        assert_eq!(outcome.exit_code(), 2);
+        assert!(matches!(
+            messages.last(),
+            Some(RunnerMessage::Aborted(reason)) if reason == "Worker failed: boom"
+        ));
+        assert_eq!(backend.calls(), vec![(AgentRole::Worker, 1)]);
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn command_verifier_passes_on_exit_zero_and_feeds_back_its_output() {
+        let backend = MockBackend::new()
+            .worker(Some(2), MockAction::Append("=== worker ===\nfixed\n".to_string()))
+            .verifier("a", None, MockAction::Check);
+        let check = Verifier {
+            name: "grep".to_string(),
+            prompt: "grep -q fixed \"$VERIFIERS_FILE\" || { echo not fixed yet; exit 1; }".to_string(),
+            enabled: true,
+            kind: VerifierKind::Command,
+            files: Vec::new(),
+            gate: false,
+        };
+        let options = RunOptions {
+            concurrency: 1,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, backend, messages) = run_verifiers(backend, vec![verifier("a"), check], options).await;
+
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![1, 2]);
+        let contents = fm.read_contents().unwrap();
+        assert!(contents.starts_with("[x] a\n[x] grep\n"));
+        assert!(contents.contains("=== grep ===\n`grep -q fixed"));
+        assert!(contents.contains("failed (exit status: 1):\nnot fixed yet\n=== worker ===\nfixed\n"));
+        // The command never goes through the agent backend.
+        assert!(!backend.calls().contains(&(AgentRole::Verifier("grep".to_string()), 1)));
+        let _ = std::fs::remove_file(&fm.path);
+    }
+
+    #[tokio::test]
+    async fn assertion_verifiers_check_files_without_an_agent() {
+        let json = std::env::temp_dir().join(format!("verifiers_test_{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&json, "{\"todo\": \"TODO: more\"}").unwrap();
+        let assert = |name: &str, body: String| Verifier {
+            name: name.to_string(),
+            prompt: body,
+            enabled: true,
+            kind: VerifierKind::Assert,
+            files: Vec::new(),
+            gate: false,
+        };
+        let verifiers = vec![
+            assert("valid", format!("exists {0}\njson {0}", json.display())),
+            assert("done", format!("not_contains {} TODO", json.display())),
+            assert("typo", "exist foo".to_string()),
+        ];
+        let options = RunOptions {
+            max_iterations: 1,
+            ..RunOptions::default()
+        };
+        let (outcome, fm, backend, messages) = run_verifiers(MockBackend::new(), verifiers, options).await;
+
+        assert_eq!(outcome, RunOutcome::MaxIterations);
+        let statuses: Vec<(usize, VerifierStatus)> = messages
+            .iter()
+            .filter_map(|m| match m {
+                RunnerMessage::VerifierStatusUpdate { index, status } if *status != VerifierStatus::Running => {
+                    Some((*index, status.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert!(statuses.contains(&(0, VerifierStatus::Passed)));
+        assert!(statuses.contains(&(1, VerifierStatus::Failed)));
+        assert!(statuses.contains(&(2, VerifierStatus::Failed)));
+        let contents = fm.read_contents().unwrap();
+        assert!(contents.contains(&format!("=== done ===\n- {} contains \"TODO\" (line 1)\n", json.display())));
+        assert!(errors(&messages)[0].starts_with("Verifier 'typo' has invalid assertions: unknown assertion 'exist'"));
+        assert_eq!(backend.calls(), vec![(AgentRole::Worker, 1)]);
+        let _ = std::fs::remove_file(&fm.path);
+        let _ = std::fs::remove_file(&json);
+    }
+
+    #[tokio::test]
+    async fn verifiers_run_in_the_working_directory() {
+        let dir = std::env::temp_dir().join(format!("verifiers_cwd_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("marker"), "").unwrap();
+        let verifiers = vec![
+            Verifier {
+                name: "cmd".to_string(),
+                prompt: "test -f marker".to_string(),
+                enabled: true,
+                kind: VerifierKind::Command,
+                files: Vec::new(),
+                gate: false,
+            },
+            Verifier {
+                name: "file".to_string(),
+                prompt: "exists marker".to_string(),
+                enabled: true,
+                kind: VerifierKind::Assert,
+                files: Vec::new(),
+                gate: false,
+            },
+        ];
+        let options = RunOptions {
+            max_iterations: 1,
+            working_dir: dir.clone(),
+            ..RunOptions::default()
+        };
+        let (outcome, fm, _, _) = run_verifiers(MockBackend::new(), verifiers, options).await;
+
+        assert_eq!(outcome, RunOutcome::Passed);
+        let _ = std::fs::remove_file(&fm.path);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn command_feedback_keeps_the_tail_of_long_output() {
+        let output = CommandOutput {
+            success: false,
+            status: "exit status: 1".to_string(),
+            output: (1..=MAX_COMMAND_OUTPUT_LINES + 2).map(|i| format!("line {}\n", i)).collect(),
+        };
+        let section = command_section("tests", "make test", &output);
+        assert!(section.starts_with("=== tests ===\n`make test` failed (exit status: 1):\n... (2 earlier lines omitted)\nline 3\n"));
+        assert!(section.ends_with(&format!("line {}\n", MAX_COMMAND_OUTPUT_LINES + 2)));
+    }
+
+    #[tokio::test]
+    async fn verifier_failure_is_reported_and_retried() {
+        let backend = MockBackend::new()
+            .verifier("a", Some(1), MockAction::Fail("crashed".to_string()))
+            .verifier("a", Some(2), MockAction::Check);
+        let (outcome, fm, _, messages) = run(backend, &["a"]).await;
+
+        assert_eq!(outcome, RunOutcome::Passed);
+        assert_eq!(iterations(&messages), vec![1, 2]);
+        assert_eq!(
+            errors(&messages),
+            vec!["Verifier 'a' failed to run: crashed".to_string()]
+        );
+        assert!(matches!(messages.last(), Some(RunnerMessage::Done)));
+        let _ = std::fs::remove_file(&fm.path);
+    }
 }
@@ -0,0 +1,107 @@
+use crate::file_manager::{parse_checkbox_line, section_header, sections};
+
+/// An agent edited the shared file outside the region it is allowed to touch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub details: String,
+    /// The file contents to restore: the snapshot plus whatever the agent was allowed to add.
+    pub repaired: String,
+}
+
+/// The worker may only append below the existing contents.
+pub fn check_worker(before: &str, after: &str) -> Option<Violation> {
+    if after.starts_with(before) {
+        return None;
+    }
+    let mut repaired = before.to_string();
+    for section in sections(after, "worker")
+        .into_iter()
+        .skip(sections(before, "worker").len())
+    {
+        if !repaired.ends_with('\n') {
+            repaired.push('\n');
+        }
+        repaired.push_str(&section);
+    }
+    Some(Violation {
+        details: format!("edited existing content ({})", first_difference(before, after)),
+        repaired,
+    })
+}
+
+/// A verifier may only touch its own checkbox line and its own `=== name ===` sections.
+/// Returns a description of the first illegal edit, if any.
+pub fn check_verifier(before: &str, after: &str, name: &str) -> Option<String> {
+    let before = outside_region(before, name);
+    let after = outside_region(after, name);
+    (before != after).then(|| format!("edited outside its own checkbox and section ({})", first_difference(&before, &after)))
+}
+
+/// The file with `name`'s checkbox line and sections removed.
+fn outside_region(contents: &str, name: &str) -> String {
+    let mut in_own_section = false;
+    contents
+        .split_inclusive('\n')
+        .filter(|line| {
+            let body = line.trim_end_matches('\n');
+            if let Some(header) = section_header(body) {
+                in_own_section = header == name;
+            }
+            let own_checkbox = parse_checkbox_line(body).is_some_and(|(checkbox, _)| checkbox == name);
+            !in_own_section && !own_checkbox
+        })
+        .collect()
+}
+
+/// Describe the first line that differs between two versions of the file.
+fn first_difference(before: &str, after: &str) -> String {
+    let mut before_lines = before.lines();
+    let mut after_lines = after.lines();
+    let mut line = 1;
+    loop {
+        match (before_lines.next(), after_lines.next()) {
+            (Some(b), Some(a)) if a == b => line += 1,
+            (Some(b), Some(a)) => return format!("line {}: {:?} -> {:?}", line, b, a),
+            (Some(b), None) => return format!("line {}: {:?} removed", line, b),
+            (None, Some(a)) => return format!("line {}: {:?} added", line, a),
+            (None, None) => return "whitespace changed".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "[] lint\n[] tests\n\nbuild it\n=== lint ===\nold\n";
+
+    #[test]
+    fn worker_may_append() {
+        let after = format!("{}=== worker ===\ndid it\n", BEFORE);
+        assert_eq!(check_worker(BEFORE, &after), None);
+    }
+
+    #[test]
+    fn worker_edits_are_reverted_but_its_section_is_kept() {
+        let after = "[x] lint\n[x] tests\n\nbuild it\n=== worker ===\ndid it\n";
+        let violation = check_worker(BEFORE, after).unwrap();
+        assert_eq!(violation.details, r#"edited existing content (line 1: "[] lint" -> "[x] lint")"#);
+        assert_eq!(violation.repaired, format!("{}=== worker ===\ndid it\n", BEFORE));
+    }
+
+    #[test]
+    fn verifier_may_edit_its_own_region() {
+        let after = "[x] lint\n[] tests\n\nbuild it\n=== lint ===\nrewritten\n=== lint ===\nmore\n";
+        assert_eq!(check_verifier(BEFORE, after, "lint"), None);
+    }
+
+    #[test]
+    fn verifier_may_not_touch_other_checkboxes_or_the_prompt() {
+        let ticked = "[] lint\n[x] tests\n\nbuild it\n=== lint ===\nold\n";
+        assert!(check_verifier(BEFORE, ticked, "lint").is_some());
+        let prompt = "[] lint\n[] tests\n\nbuild something else\n=== lint ===\nold\n";
+        assert!(check_verifier(BEFORE, prompt, "lint").is_some());
+        let forged = format!("{}=== tests ===\nall good\n", BEFORE);
+        assert!(check_verifier(BEFORE, &forged, "lint").is_some());
+    }
+}
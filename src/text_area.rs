@@ -0,0 +1,482 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthChar;
+
+/// How many edits Ctrl+Z can take back.
+const MAX_UNDO: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Replace,
+}
+
+/// A text input for the setup screen. Multi-line areas take newlines; single-line ones
+/// turn pasted newlines into spaces. Either way the text word-wraps when drawn, and
+/// Up/Down move through the wrapped rows.
+#[derive(Clone, Debug)]
+pub struct TextArea {
+    text: String,
+    /// Byte offset into `text`, always on a char boundary.
+    cursor: usize,
+    multiline: bool,
+    /// Earlier (text, cursor) states, newest last.
+    undo: Vec<(String, usize)>,
+    /// The kind of the last edit, so a run of typing undoes as one step.
+    last_edit: Option<EditKind>,
+    /// Column Up/Down aim for, kept across consecutive vertical moves.
+    goal_column: Option<u16>,
+    /// Inner width the area was last drawn at; Up/Down need the wrapped layout.
+    width: Cell<u16>,
+}
+
+impl TextArea {
+    pub fn multi_line() -> Self {
+        Self::new(true)
+    }
+
+    pub fn single_line() -> Self {
+        Self::new(false)
+    }
+
+    fn new(multiline: bool) -> Self {
+        Self {
+            text: String::new(),
+            cursor: 0,
+            multiline,
+            undo: Vec::new(),
+            last_edit: None,
+            goal_column: None,
+            width: Cell::new(0),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the whole text, leaving the cursor at the end. Undoable.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if text != self.text {
+            self.checkpoint(EditKind::Replace);
+            self.text = text;
+        }
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+    }
+
+    /// Handle an editing or movement key. Returns whether the text changed. Plain
+    /// Enter is left to the caller; Shift/Alt+Enter insert a newline in multi-line areas.
+    pub fn input(&mut self, key: KeyEvent) -> bool {
+        let before = self.undo.len();
+        let text_before = self.text.len();
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Left if ctrl || alt => self.move_to(self.word_start()),
+            KeyCode::Right if ctrl || alt => self.move_to(self.word_end()),
+            KeyCode::Char('b') if alt => self.move_to(self.word_start()),
+            KeyCode::Char('f') if alt => self.move_to(self.word_end()),
+            KeyCode::Left => self.move_to(self.prev_boundary()),
+            KeyCode::Right => self.move_to(self.next_boundary()),
+            KeyCode::Home if ctrl => self.move_to(0),
+            KeyCode::End if ctrl => self.move_to(self.text.len()),
+            KeyCode::Home => self.move_to(self.line_start()),
+            KeyCode::End => self.move_to(self.line_end()),
+            KeyCode::Up => self.move_vertically(false),
+            KeyCode::Down => self.move_vertically(true),
+            KeyCode::Backspace if ctrl || alt => self.delete_range(self.word_start(), self.cursor),
+            KeyCode::Char('w') if ctrl => self.delete_range(self.word_start(), self.cursor),
+            KeyCode::Backspace => self.delete_range(self.prev_boundary(), self.cursor),
+            KeyCode::Delete => self.delete_range(self.cursor, self.next_boundary()),
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Enter if (alt || key.modifiers.contains(KeyModifiers::SHIFT)) && self.multiline => {
+                self.insert_str("\n")
+            }
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_char(c),
+            _ => {}
+        }
+        self.undo.len() != before || self.text.len() != text_before
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        if c == '\n' && !self.multiline {
+            return;
+        }
+        // Each word, with the space before it, undoes as one step.
+        if c.is_whitespace() {
+            self.last_edit = None;
+        }
+        self.checkpoint(EditKind::Insert);
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert text at the cursor, e.g. from a paste, as a single undo step.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let text = if self.multiline { text } else { text.replace('\n', " ") };
+        if text.is_empty() {
+            return;
+        }
+        self.checkpoint(EditKind::Replace);
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Put the text back as it was before the last edit.
+    pub fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo.pop() {
+            self.text = text;
+            self.cursor = cursor;
+        }
+        self.last_edit = None;
+        self.goal_column = None;
+    }
+
+    /// Move the cursor to the end of the text.
+    pub fn move_to_end(&mut self) {
+        self.move_to(self.text.len());
+    }
+
+    fn checkpoint(&mut self, kind: EditKind) {
+        if kind == EditKind::Replace || self.last_edit != Some(kind) {
+            if self.undo.len() == MAX_UNDO {
+                self.undo.remove(0);
+            }
+            self.undo.push((self.text.clone(), self.cursor));
+        }
+        self.last_edit = Some(kind);
+        self.goal_column = None;
+    }
+
+    fn move_to(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        self.last_edit = None;
+        self.goal_column = None;
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        if start < end {
+            self.checkpoint(EditKind::Delete);
+            self.text.replace_range(start..end, "");
+            self.cursor = start;
+        }
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.text[..self.cursor].char_indices().last().map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..].chars().next().map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn word_start(&self) -> usize {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end();
+        trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn word_end(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let skipped = after.len() - after.trim_start().len();
+        after[skipped..]
+            .char_indices()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(self.text.len(), |(i, _)| self.cursor + skipped + i)
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..].find('\n').map_or(self.text.len(), |i| self.cursor + i)
+    }
+
+    /// Move to the wrapped row above or below, as close to the goal column as it has.
+    fn move_vertically(&mut self, down: bool) {
+        let width = self.width.get();
+        if width == 0 {
+            return;
+        }
+        let goal = match self.goal_column {
+            Some(goal) => goal,
+            None => cursor_pos_wrapped(&self.text[..self.cursor], width).0,
+        };
+        let line_start = self.line_start();
+        let line_end = self.line_end();
+        let (_, row) = cursor_pos_wrapped(&self.text[line_start..self.cursor], width);
+        let rows = word_wrap_widths(&self.text[line_start..line_end], width).len() as u16;
+
+        let target = if down && row + 1 < rows {
+            Some((line_start, row + 1))
+        } else if down && line_end < self.text.len() {
+            Some((line_end + 1, 0))
+        } else if !down && row > 0 {
+            Some((line_start, row - 1))
+        } else if !down && line_start > 0 {
+            let prev_start = self.text[..line_start - 1].rfind('\n').map_or(0, |i| i + 1);
+            let prev_rows = word_wrap_widths(&self.text[prev_start..line_start - 1], width).len() as u16;
+            Some((prev_start, prev_rows - 1))
+        } else {
+            None
+        };
+        self.cursor = match target {
+            Some((start, row)) => self.offset_in_row(start, row, goal, width),
+            // Already on the first or last row.
+            None if down => self.text.len(),
+            None => 0,
+        };
+        self.last_edit = None;
+        self.goal_column = Some(goal);
+    }
+
+    /// The offset on wrapped row `row` of the line starting at `start` that is closest
+    /// to `goal` without passing it.
+    fn offset_in_row(&self, start: usize, row: u16, goal: u16, width: u16) -> usize {
+        let end = self.text[start..].find('\n').map_or(self.text.len(), |i| start + i);
+        let line = &self.text[start..end];
+        let mut best = None;
+        for i in line.char_indices().map(|(i, _)| i).chain(std::iter::once(line.len())) {
+            let (x, y) = cursor_pos_wrapped(&line[..i], width);
+            if y > row {
+                break;
+            }
+            if y == row {
+                if best.is_none() || x <= goal {
+                    best = Some(i);
+                }
+                if x >= goal {
+                    break;
+                }
+            }
+        }
+        start + best.unwrap_or(line.len())
+    }
+
+    /// Remember the inner width the area is drawn at.
+    pub fn set_width(&self, width: u16) {
+        self.width.set(width);
+    }
+
+    /// Cursor (column, row) within the area when wrapped at `width`.
+    pub fn cursor_position(&self, width: u16) -> (u16, u16) {
+        cursor_pos_wrapped(&self.text[..self.cursor], width)
+    }
+
+    /// Rows the text takes when wrapped at `width`.
+    pub fn row_count(&self, width: u16) -> u16 {
+        wrapped_row_count(&self.text, width)
+    }
+}
+
+/// Compute visual row widths produced by word-wrapping a single line (no newlines),
+/// matching ratatui's WordWrapper with trim=false.
+fn word_wrap_widths(line: &str, max_width: u16) -> Vec<u16> {
+    if max_width == 0 {
+        return vec![0];
+    }
+
+    let mut wrapped: Vec<u16> = Vec::new();
+    let mut line_width: u16 = 0;
+    let mut word_width: u16 = 0;
+    let mut whitespace_width: u16 = 0;
+    let mut non_ws_prev = false;
+    let mut pending_ws: VecDeque<u16> = VecDeque::new();
+    let mut has_pending_word = false;
+
+    for ch in line.chars() {
+        let is_ws = ch.is_whitespace();
+        let sym_w = UnicodeWidthChar::width(ch).unwrap_or(0) as u16;
+
+        if sym_w > max_width {
+            continue;
+        }
+
+        let word_found = non_ws_prev && is_ws;
+        let untrimmed_overflow =
+            line_width == 0 && (word_width + whitespace_width + sym_w > max_width);
+
+        // Commit pending whitespace + word to line
+        if word_found || untrimmed_overflow {
+            line_width += whitespace_width + word_width;
+            whitespace_width = 0;
+            word_width = 0;
+            pending_ws.clear();
+            has_pending_word = false;
+        }
+
+        let line_full = line_width >= max_width;
+        let pending_overflow =
+            sym_w > 0 && line_width + whitespace_width + word_width >= max_width;
+
+        if line_full || pending_overflow {
+            let mut remaining = max_width.saturating_sub(line_width);
+            wrapped.push(line_width);
+            line_width = 0;
+
+            // Remove whitespace that fits in the remaining space of the pushed line
+            while let Some(&w) = pending_ws.front() {
+                if w > remaining {
+                    break;
+                }
+                whitespace_width -= w;
+                remaining -= w;
+                pending_ws.pop_front();
+            }
+
+            // Skip first whitespace after a line break
+            if is_ws && pending_ws.is_empty() {
+                non_ws_prev = false;
+                continue;
+            }
+        }
+
+        if is_ws {
+            whitespace_width += sym_w;
+            pending_ws.push_back(sym_w);
+        } else {
+            word_width += sym_w;
+            has_pending_word = true;
+        }
+
+        non_ws_prev = !is_ws;
+    }
+
+    // Finalization (matches ratatui's process_input)
+    if line_width == 0 && !has_pending_word && !pending_ws.is_empty() {
+        wrapped.push(0);
+    }
+    let final_width = line_width + whitespace_width + word_width;
+    if final_width > 0 || has_pending_word {
+        wrapped.push(final_width);
+    }
+    if wrapped.is_empty() {
+        wrapped.push(0);
+    }
+
+    wrapped
+}
+
+/// Compute (x_offset, y_offset) cursor position at the end of text rendered with
+/// ratatui's Wrap { trim: false } word wrapping, matching the visual layout exactly.
+fn cursor_pos_wrapped(text: &str, max_width: u16) -> (u16, u16) {
+    if max_width == 0 {
+        return (0, 0);
+    }
+
+    let mut total_rows: u16 = 0;
+    let natural_lines: Vec<&str> = text.split('\n').collect();
+
+    for (i, natural_line) in natural_lines.iter().enumerate() {
+        let widths = word_wrap_widths(natural_line, max_width);
+        if i < natural_lines.len() - 1 {
+            total_rows += widths.len() as u16;
+        } else {
+            total_rows += widths.len().saturating_sub(1) as u16;
+            let last_width = widths.last().copied().unwrap_or(0);
+            return (last_width, total_rows);
+        }
+    }
+
+    (0, total_rows)
+}
+
+/// Count total visual rows after word-wrapping text with trim=false.
+fn wrapped_row_count(text: &str, max_width: u16) -> u16 {
+    text.split('\n')
+        .map(|line| word_wrap_widths(line, max_width).len() as u16)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn type_str(area: &mut TextArea, text: &str) {
+        for c in text.chars() {
+            area.input(key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn edits_at_the_cursor_and_undoes_word_by_word() {
+        let mut area = TextArea::multi_line();
+        type_str(&mut area, "fix the parser");
+        area.input(key(KeyCode::Left, KeyModifiers::CONTROL));
+        assert_eq!(area.cursor, "fix the ".len());
+        area.input(key(KeyCode::Left, KeyModifiers::CONTROL));
+        area.input(key(KeyCode::Delete, KeyModifiers::NONE));
+        assert_eq!(area.text(), "fix he parser");
+        area.input(key(KeyCode::End, KeyModifiers::NONE));
+        area.input(key(KeyCode::Enter, KeyModifiers::ALT));
+        type_str(&mut area, "é");
+        area.input(key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(area.text(), "fix he parser\n");
+
+        area.input(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(area.text(), "fix he parser\né");
+        area.input(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        area.input(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(area.text(), "fix he parser");
+        area.input(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(area.text(), "fix the parser");
+        area.input(key(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(area.text(), "fix the");
+    }
+
+    #[test]
+    fn up_and_down_follow_wrapped_rows() {
+        let mut area = TextArea::multi_line();
+        area.set_text("aaaa bbbb cccc\ndd");
+        area.set_width(10);
+        // Wrapped as "aaaa bbbb " / "cccc" / "dd".
+        area.input(key(KeyCode::Home, KeyModifiers::CONTROL));
+        area.input(key(KeyCode::Right, KeyModifiers::NONE));
+        area.input(key(KeyCode::Right, KeyModifiers::NONE));
+        area.input(key(KeyCode::Right, KeyModifiers::NONE));
+        area.input(key(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(area.cursor, "aaaa bbbb ccc".len());
+        area.input(key(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(area.cursor, "aaaa bbbb cccc\ndd".len());
+        area.input(key(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(area.cursor, "aaaa bbbb ccc".len());
+        area.input(key(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(area.cursor, 3);
+        area.input(key(KeyCode::Up, KeyModifiers::NONE));
+        assert_eq!(area.cursor, 0);
+    }
+
+    #[test]
+    fn single_line_pastes_newlines_as_spaces() {
+        let mut area = TextArea::single_line();
+        area.insert_str("cargo\r\ntest");
+        area.input(key(KeyCode::Enter, KeyModifiers::SHIFT));
+        assert_eq!(area.text(), "cargo test");
+
+        let mut area = TextArea::multi_line();
+        area.insert_str("exists a\r\njson b");
+        assert_eq!(area.text(), "exists a\njson b");
+        area.undo();
+        assert_eq!(area.text(), "");
+    }
+}
@@ -1,140 +1,26 @@
-use std::collections::VecDeque;
-
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
-
-use crate::app::{App, Screen, ScrollFocus, SetupFocus, VerifierStatus};
-
-/// Compute visual row widths produced by word-wrapping a single line (no newlines),
-/// matching ratatui's WordWrapper with trim=false.
-fn word_wrap_widths(line: &str, max_width: u16) -> Vec<u16> {
-    if max_width == 0 {
-        return vec![0];
-    }
-
-    let mut wrapped: Vec<u16> = Vec::new();
-    let mut line_width: u16 = 0;
-    let mut word_width: u16 = 0;
-    let mut whitespace_width: u16 = 0;
-    let mut non_ws_prev = false;
-    let mut pending_ws: VecDeque<u16> = VecDeque::new();
-    let mut has_pending_word = false;
-
-    for ch in line.chars() {
-        let is_ws = ch.is_whitespace();
-        let sym_w = UnicodeWidthChar::width(ch).unwrap_or(0) as u16;
-
-        if sym_w > max_width {
-            continue;
-        }
-
-        let word_found = non_ws_prev && is_ws;
-        let untrimmed_overflow =
-            line_width == 0 && (word_width + whitespace_width + sym_w > max_width);
-
-        // Commit pending whitespace + word to line
-        if word_found || untrimmed_overflow {
-            line_width += whitespace_width + word_width;
-            whitespace_width = 0;
-            word_width = 0;
-            pending_ws.clear();
-            has_pending_word = false;
-        }
-
-        let line_full = line_width >= max_width;
-        let pending_overflow =
-            sym_w > 0 && line_width + whitespace_width + word_width >= max_width;
-
-        if line_full || pending_overflow {
-            let mut remaining = max_width.saturating_sub(line_width);
-            wrapped.push(line_width);
-            line_width = 0;
-
-            // Remove whitespace that fits in the remaining space of the pushed line
-            while let Some(&w) = pending_ws.front() {
-                if w > remaining {
-                    break;
-                }
-                whitespace_width -= w;
-                remaining -= w;
-                pending_ws.pop_front();
-            }
-
-            // Skip first whitespace after a line break
-            if is_ws && pending_ws.is_empty() {
-                non_ws_prev = false;
-                continue;
-            }
-        }
+use unicode_width::UnicodeWidthStr;
 
-        if is_ws {
-            whitespace_width += sym_w;
-            pending_ws.push_back(sym_w);
-        } else {
-            word_width += sym_w;
-            has_pending_word = true;
-        }
+use crate::app::{App, Screen, ScrollFocus, SetupFocus, SetupOption, VerifierKind, VerifierStatus};
+use crate::diff::DiffLine;
+use crate::history::{self, Outcome, RunRecord};
+use crate::text_area::TextArea;
 
-        non_ws_prev = !is_ws;
-    }
-
-    // Finalization (matches ratatui's process_input)
-    if line_width == 0 && !has_pending_word && !pending_ws.is_empty() {
-        wrapped.push(0);
-    }
-    let final_width = line_width + whitespace_width + word_width;
-    if final_width > 0 || has_pending_word {
-        wrapped.push(final_width);
-    }
-    if wrapped.is_empty() {
-        wrapped.push(0);
-    }
-
-    wrapped
-}
-
-/// Compute (x_offset, y_offset) cursor position at the end of text rendered with
-/// ratatui's Wrap { trim: false } word wrapping, matching the visual layout exactly.
-fn cursor_pos_wrapped(text: &str, max_width: u16) -> (u16, u16) {
-    if max_width == 0 {
-        return (0, 0);
-    }
-
-    let mut total_rows: u16 = 0;
-    let natural_lines: Vec<&str> = text.split('\n').collect();
-
-    for (i, natural_line) in natural_lines.iter().enumerate() {
-        let widths = word_wrap_widths(natural_line, max_width);
-        if i < natural_lines.len() - 1 {
-            total_rows += widths.len() as u16;
-        } else {
-            total_rows += widths.len().saturating_sub(1) as u16;
-            let last_width = widths.last().copied().unwrap_or(0);
-            return (last_width, total_rows);
-        }
-    }
-
-    (0, total_rows)
-}
-
-/// Count total visual rows after word-wrapping text with trim=false.
-fn wrapped_row_count(text: &str, max_width: u16) -> u16 {
-    text.split('\n')
-        .map(|line| word_wrap_widths(line, max_width).len() as u16)
-        .sum()
-}
+/// Most rows the verifier prompt input grows to before it scrolls.
+const MAX_VERIFIER_PROMPT_ROWS: u16 = 8;
 
 pub fn draw(frame: &mut Frame, app: &App) {
     match app.screen {
         Screen::Setup => draw_setup(frame, app),
-        Screen::Running | Screen::Done => draw_running(frame, app),
+        Screen::Running | Screen::Done | Screen::Failed | Screen::Aborted => draw_running(frame, app),
+        Screen::History => draw_history(frame, app),
     }
 }
 
@@ -143,8 +29,11 @@ fn draw_setup(frame: &mut Frame, app: &App) {
 
     // Calculate dynamic heights for verifier input fields based on word wrapping
     let inner_width = area.width.saturating_sub(2); // subtract borders
-    let name_rows = wrapped_row_count(&app.verifier_name_input, inner_width);
-    let vprompt_rows = wrapped_row_count(&app.verifier_prompt_input, inner_width);
+    let name_rows = app.verifier_name_input.row_count(inner_width);
+    let vprompt_rows = app
+        .verifier_prompt_input
+        .row_count(inner_width)
+        .min(MAX_VERIFIER_PROMPT_ROWS);
 
     // Build help spans early so we can calculate dynamic height
     let can_start = app.can_start();
@@ -152,7 +41,7 @@ fn draw_setup(frame: &mut Frame, app: &App) {
         Span::styled(" Ctrl+S: Start ", Style::default().fg(Color::Green))
     } else {
         Span::styled(
-            " Ctrl+S: Start (need prompt + enabled verifier) ",
+            " Ctrl+S: Start (need prompt + enabled verifier + existing directory) ",
             Style::default().fg(Color::DarkGray),
         )
     };
@@ -171,18 +60,63 @@ fn draw_setup(frame: &mut Frame, app: &App) {
             Style::default().fg(Color::Cyan),
         ));
         help_spans.push(Span::raw(" | "));
+        help_spans.push(Span::styled(
+            " g: Gate ",
+            Style::default().fg(Color::Cyan),
+        ));
+        help_spans.push(Span::raw(" | "));
+        help_spans.push(Span::styled(
+            " Enter: Edit ",
+            Style::default().fg(Color::Cyan),
+        ));
+        help_spans.push(Span::raw(" | "));
+        help_spans.push(Span::styled(
+            " Shift+Up/Down: Move ",
+            Style::default().fg(Color::Cyan),
+        ));
+        help_spans.push(Span::raw(" | "));
         help_spans.push(Span::styled(
             " Ctrl+D: Remove ",
             Style::default().fg(Color::Cyan),
         ));
         help_spans.push(Span::raw(" | "));
-    } else {
+    } else if app.setup_focus == SetupFocus::Options {
+        help_spans.push(Span::styled(
+            " Up/Down: Select ",
+            Style::default().fg(Color::Cyan),
+        ));
+        help_spans.push(Span::raw(" | "));
         help_spans.push(Span::styled(
-            " Enter: Add verifier ",
+            " Left/Right: Change ",
             Style::default().fg(Color::Cyan),
         ));
         help_spans.push(Span::raw(" | "));
+    } else {
+        let enter_label = if app.editing_verifier.is_some() {
+            " Enter: Save verifier "
+        } else {
+            " Enter: Add verifier "
+        };
+        help_spans.push(Span::styled(enter_label, Style::default().fg(Color::Cyan)));
+        help_spans.push(Span::raw(" | "));
+        if app.editing_verifier.is_some() {
+            help_spans.push(Span::styled(" Esc: Cancel edit ", Style::default().fg(Color::Cyan)));
+            help_spans.push(Span::raw(" | "));
+        }
+        if matches!(app.setup_focus, SetupFocus::VerifierName | SetupFocus::VerifierPrompt) {
+            help_spans.push(Span::styled(
+                " Ctrl+T: Verifier kind ",
+                Style::default().fg(Color::Cyan),
+            ));
+            help_spans.push(Span::raw(" | "));
+        }
+    }
+    if app.editable_text().is_some() {
+        help_spans.push(Span::styled(" Ctrl+G: $EDITOR ", Style::default().fg(Color::Cyan)));
+        help_spans.push(Span::raw(" | "));
     }
+    help_spans.push(Span::styled(" Ctrl+O: Suites ", Style::default().fg(Color::Cyan)));
+    help_spans.push(Span::raw(" | "));
     help_spans.push(start_hint);
     if !app.prompt_history.is_empty() && app.setup_focus == SetupFocus::Prompt {
         help_spans.push(Span::raw(" | "));
@@ -197,7 +131,7 @@ fn draw_setup(frame: &mut Frame, app: &App) {
     // Calculate help bar height: text rows + 1 for top border
     let help_text_width: usize = help_spans.iter().map(|s| s.content.width()).sum();
     let help_bar_rows = if area.width > 0 {
-        ((help_text_width.max(1) + area.width as usize - 1) / area.width as usize) as u16
+        help_text_width.max(1).div_ceil(area.width as usize) as u16
     } else {
         1
     };
@@ -207,17 +141,37 @@ fn draw_setup(frame: &mut Frame, app: &App) {
         .constraints([
             Constraint::Length(3),                // Title
             Constraint::Min(6),                  // Prompt input
+            Constraint::Length(3),                // Working directory input
             Constraint::Length(name_rows + 2),    // Verifier name input (dynamic)
             Constraint::Length(vprompt_rows + 2), // Verifier prompt input (dynamic)
             Constraint::Min(4),                  // Verifier list
+            Constraint::Length(SetupOption::ALL.len() as u16 + 2), // Options
             Constraint::Length(help_bar_rows + 1), // Help bar (dynamic + top border)
         ])
         .split(area);
 
-    // Title
-    let title = Paragraph::new("Verifiers TUI")
-        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::BOTTOM));
+    // Title, plus where the settings came from
+    let mut title_spans = vec![Span::styled(
+        "Verifiers TUI",
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    )];
+    if let Some(error) = &app.editor_error {
+        title_spans.push(Span::raw("  "));
+        title_spans.push(Span::styled(error.clone(), Style::default().fg(Color::Red)));
+    } else if let Some(error) = &app.project_config_error {
+        title_spans.push(Span::raw("  "));
+        title_spans.push(Span::styled(error.clone(), Style::default().fg(Color::Red)));
+    } else if let Some((path, _)) = &app.project_config {
+        title_spans.push(Span::raw("  "));
+        title_spans.push(Span::styled(
+            format!("Project config: {}", path.display()),
+            Style::default().fg(Color::DarkGray),
+        ));
+        if app.project_commands_ignored {
+            title_spans.push(Span::styled(" (commands ignored: not trusted)", Style::default().fg(Color::Yellow)));
+        }
+    }
+    let title = Paragraph::new(Line::from(title_spans)).block(Block::default().borders(Borders::BOTTOM));
     frame.render_widget(title, chunks[0]);
 
     // Prompt input
@@ -230,10 +184,24 @@ fn draw_setup(frame: &mut Frame, app: &App) {
         .title(" Prompt (what the worker should do) ")
         .borders(Borders::ALL)
         .border_style(prompt_style);
-    let prompt_text = Paragraph::new(app.prompt_input.as_str())
-        .block(prompt_block)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(prompt_text, chunks[1]);
+    draw_text_area(frame, chunks[1], &app.prompt_input, prompt_block, app.setup_focus == SetupFocus::Prompt);
+
+    // Working directory input
+    let dir_style = if app.setup_focus == SetupFocus::WorkingDir {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    let dir_title = if app.working_dir().is_some() {
+        " Working Directory (where the agents run) "
+    } else {
+        " Working Directory (not an existing directory) "
+    };
+    let dir_block = Block::default()
+        .title(dir_title)
+        .borders(Borders::ALL)
+        .border_style(dir_style);
+    draw_text_area(frame, chunks[2], &app.working_dir_input, dir_block, app.setup_focus == SetupFocus::WorkingDir);
 
     // Verifier name input
     let name_style = if app.setup_focus == SetupFocus::VerifierName {
@@ -241,14 +209,21 @@ fn draw_setup(frame: &mut Frame, app: &App) {
     } else {
         Style::default().fg(Color::White)
     };
+    let name_title = match app.editing_verifier {
+        Some(i) => format!(" Verifier Name (editing #{}) ", i + 1),
+        None => " Verifier Name ".to_string(),
+    };
     let name_block = Block::default()
-        .title(" Verifier Name ")
+        .title(name_title)
         .borders(Borders::ALL)
         .border_style(name_style);
-    let name_text = Paragraph::new(app.verifier_name_input.as_str())
-        .block(name_block)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(name_text, chunks[2]);
+    draw_text_area(
+        frame,
+        chunks[3],
+        &app.verifier_name_input,
+        name_block,
+        app.setup_focus == SetupFocus::VerifierName,
+    );
 
     // Verifier prompt input
     let vprompt_style = if app.setup_focus == SetupFocus::VerifierPrompt {
@@ -257,13 +232,16 @@ fn draw_setup(frame: &mut Frame, app: &App) {
         Style::default().fg(Color::White)
     };
     let vprompt_block = Block::default()
-        .title(" Verifier Prompt ")
+        .title(format!(" {} ", app.verifier_kind_input.input_title()))
         .borders(Borders::ALL)
         .border_style(vprompt_style);
-    let vprompt_text = Paragraph::new(app.verifier_prompt_input.as_str())
-        .block(vprompt_block)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(vprompt_text, chunks[3]);
+    draw_text_area(
+        frame,
+        chunks[4],
+        &app.verifier_prompt_input,
+        vprompt_block,
+        app.setup_focus == SetupFocus::VerifierPrompt,
+    );
 
     // Verifier list
     let list_focused = app.setup_focus == SetupFocus::VerifierList;
@@ -279,7 +257,13 @@ fn draw_setup(frame: &mut Frame, app: &App) {
         .enumerate()
         .map(|(i, v)| {
             let checkbox = if v.enabled { "[x]" } else { "[ ]" };
-            let text = format!("  {} {}. {} — {}", checkbox, i + 1, v.name, v.prompt);
+            let body = match v.kind {
+                VerifierKind::Agent => v.prompt.clone(),
+                VerifierKind::Command => format!("$ {}", v.prompt),
+                VerifierKind::Assert => format!("assert {}", v.prompt.lines().collect::<Vec<_>>().join("; ")),
+            };
+            let gate = if v.gate { " [gate]" } else { "" };
+            let text = format!("  {} {}. {}{} — {}", checkbox, i + 1, v.name, gate, body);
             if list_focused && i == app.selected_verifier {
                 ListItem::new(text).style(
                     Style::default()
@@ -287,6 +271,8 @@ fn draw_setup(frame: &mut Frame, app: &App) {
                         .fg(Color::White)
                         .add_modifier(Modifier::BOLD),
                 )
+            } else if app.editing_verifier == Some(i) {
+                ListItem::new(text).style(Style::default().fg(Color::Yellow))
             } else if !v.enabled {
                 ListItem::new(text).style(Style::default().fg(Color::DarkGray))
             } else {
@@ -296,55 +282,215 @@ fn draw_setup(frame: &mut Frame, app: &App) {
         .collect();
     let verifier_list = List::new(verifier_items).block(
         Block::default()
-            .title(format!(" Verifiers ({}/{} enabled) ", enabled_count, app.verifiers.len()))
+            .title(format!(
+                " Verifiers: {} ({}/{} enabled) ",
+                app.suite,
+                enabled_count,
+                app.verifiers.len()
+            ))
             .borders(Borders::ALL)
             .border_style(list_border_style),
     );
-    frame.render_widget(verifier_list, chunks[4]);
+    frame.render_widget(verifier_list, chunks[5]);
+
+    // Options
+    let options_focused = app.setup_focus == SetupFocus::Options;
+    let options_border_style = if options_focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    let option_items: Vec<ListItem> = SetupOption::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let text = format!("  {:<20} < {} >", option.label(), app.option_value(*option));
+            if options_focused && i == app.selected_option {
+                ListItem::new(text).style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ListItem::new(text)
+            }
+        })
+        .collect();
+    let options_list = List::new(option_items).block(
+        Block::default()
+            .title(" Options ")
+            .borders(Borders::ALL)
+            .border_style(options_border_style),
+    );
+    frame.render_widget(options_list, chunks[6]);
 
     // Render help bar
     let help = Line::from(help_spans);
     let help_bar = Paragraph::new(help)
         .block(Block::default().borders(Borders::TOP))
         .wrap(Wrap { trim: false });
-    frame.render_widget(help_bar, chunks[5]);
+    frame.render_widget(help_bar, chunks[7]);
 
-    // Show cursor in the focused input, using word-wrap-aware positioning
-    match app.setup_focus {
-        SetupFocus::Prompt => {
-            let iw = chunks[1].width.saturating_sub(2);
-            if iw > 0 {
-                let cursor = app.prompt_cursor.min(app.prompt_input.len());
-                let (x_off, y_off) = cursor_pos_wrapped(&app.prompt_input[..cursor], iw);
-                let x = chunks[1].x + 1 + x_off;
-                let y = chunks[1].y + 1 + y_off;
-                frame.set_cursor_position((x, y));
-            }
-        }
-        SetupFocus::VerifierName => {
-            let iw = chunks[2].width.saturating_sub(2);
-            if iw > 0 {
-                let cursor = app.verifier_name_cursor.min(app.verifier_name_input.len());
-                let (x_off, y_off) = cursor_pos_wrapped(&app.verifier_name_input[..cursor], iw);
-                let x = chunks[2].x + 1 + x_off;
-                let y = chunks[2].y + 1 + y_off;
-                frame.set_cursor_position((x, y));
+    if app.suite_picker.is_some() {
+        draw_suite_picker(frame, app);
+    }
+    draw_trust_prompt(frame, app);
+}
+
+/// Ask whether to trust the commands the project config runs, over the setup screen.
+fn draw_trust_prompt(frame: &mut Frame, app: &App) {
+    let (Some(project), Some((path, _))) = (&app.trust_prompt, &app.project_config) else {
+        return;
+    };
+    let commands = project.commands();
+    let area = frame.area();
+    let width = area.width.saturating_sub(4).min(80);
+    let height = (commands.len() as u16 + 7).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .title(" Trust this project? ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Commands
+            Constraint::Length(2), // Help
+        ])
+        .split(inner);
+
+    let mut lines = vec![
+        Line::raw(format!(" {} runs these commands:", path.display())),
+        Line::raw(""),
+    ];
+    lines.extend(
+        commands
+            .into_iter()
+            .map(|command| Line::styled(format!("   {}", command), Style::default().fg(Color::White))),
+    );
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let help = Line::from(vec![
+        Span::styled(" y: Trust and remember ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" n/Esc: Ignore its commands this time ", Style::default().fg(Color::Cyan)),
+    ]);
+    frame.render_widget(
+        Paragraph::new(help)
+            .block(Block::default().borders(Borders::TOP))
+            .wrap(Wrap { trim: false }),
+        chunks[1],
+    );
+}
+
+/// Draw a text input inside `block`, scrolled to keep the cursor row in view, and put
+/// the terminal cursor in it when focused. Uses the same word wrapping as the input's
+/// own Up/Down movement.
+fn draw_text_area(frame: &mut Frame, area: Rect, input: &TextArea, block: Block, focused: bool) {
+    let inner = block.inner(area);
+    input.set_width(inner.width);
+    let (x, y) = input.cursor_position(inner.width);
+    let scroll = y.saturating_sub(inner.height.saturating_sub(1));
+    let paragraph = Paragraph::new(input.text())
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+    frame.render_widget(paragraph, area);
+    if focused && inner.width > 0 && inner.height > 0 {
+        frame.set_cursor_position((inner.x + x.min(inner.width - 1), inner.y + y - scroll));
+    }
+}
+
+/// Popup over the setup screen listing the saved suites, with a field for naming a
+/// new one.
+fn draw_suite_picker(frame: &mut Frame, app: &App) {
+    let Some(picker) = &app.suite_picker else {
+        return;
+    };
+    let names = app.suite_names();
+    let area = frame.area();
+    let width = area.width.saturating_sub(4).min(60);
+    let height = (names.len() as u16 + 6).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    frame.render_widget(Clear, popup);
+
+    let block = Block::default()
+        .title(" Suites ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup);
+    frame.render_widget(block, popup);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Suites
+            Constraint::Length(1), // New suite name
+            Constraint::Length(2), // Help
+        ])
+        .split(inner);
+
+    let items: Vec<ListItem> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let marker = if *name == app.suite { "*" } else { " " };
+            let mut text = format!(" {} {}", marker, name);
+            if app.is_project_suite(name) {
+                text.push_str(" (project)");
             }
-        }
-        SetupFocus::VerifierPrompt => {
-            let iw = chunks[3].width.saturating_sub(2);
-            if iw > 0 {
-                let cursor = app.verifier_prompt_cursor.min(app.verifier_prompt_input.len());
-                let (x_off, y_off) = cursor_pos_wrapped(&app.verifier_prompt_input[..cursor], iw);
-                let x = chunks[3].x + 1 + x_off;
-                let y = chunks[3].y + 1 + y_off;
-                frame.set_cursor_position((x, y));
+            if i == picker.selected {
+                ListItem::new(text).style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ListItem::new(text)
             }
-        }
-        SetupFocus::VerifierList => {
-            // No text cursor in the list view
-        }
-    }
+        })
+        .collect();
+    frame.render_widget(List::new(items), chunks[0]);
+
+    let label = " New suite: ";
+    let input = Line::from(vec![
+        Span::styled(label, Style::default().fg(Color::Cyan)),
+        Span::raw(picker.new_name.clone()),
+    ]);
+    frame.render_widget(Paragraph::new(input), chunks[1]);
+    let x_off = (label.width() + picker.new_name.width()) as u16;
+    frame.set_cursor_position((chunks[1].x + x_off.min(chunks[1].width.saturating_sub(1)), chunks[1].y));
+
+    let help = Line::from(vec![
+        Span::styled(" Enter: Switch/create ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" Ctrl+D: Delete ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" Esc: Close ", Style::default().fg(Color::Cyan)),
+    ]);
+    frame.render_widget(
+        Paragraph::new(help)
+            .block(Block::default().borders(Borders::TOP))
+            .wrap(Wrap { trim: false }),
+        chunks[2],
+    );
 }
 
 fn draw_running(frame: &mut Frame, app: &App) {
@@ -360,8 +506,20 @@ fn draw_running(frame: &mut Frame, app: &App) {
             " Up/Down: Scroll ",
             Style::default().fg(Color::Cyan),
         ),
+        Span::raw(" | "),
+        Span::styled(
+            if app.show_diff { " d: Show file " } else { " d: Show diff " },
+            Style::default().fg(Color::Cyan),
+        ),
     ];
-    if app.screen == Screen::Done {
+    if app.show_diff {
+        help_spans.push(Span::raw(" | "));
+        help_spans.push(Span::styled(
+            " Left/Right: Step  [/]: Compare from ",
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    if app.screen.is_finished() {
         help_spans.push(Span::raw(" | "));
         help_spans.push(Span::styled(
             " Ctrl+E: Edit prompt ",
@@ -372,20 +530,42 @@ fn draw_running(frame: &mut Frame, app: &App) {
             " Ctrl+N: New prompt ",
             Style::default().fg(Color::Green),
         ));
+        if let Some((branch, _)) = &app.worktree {
+            if app.screen == Screen::Done {
+                help_spans.push(Span::raw(" | "));
+                help_spans.push(Span::styled(
+                    format!(" m: Merge {} ", branch),
+                    Style::default().fg(Color::Green),
+                ));
+            }
+            help_spans.push(Span::raw(" | "));
+            help_spans.push(Span::styled(
+                " l: Leave branch ",
+                Style::default().fg(Color::Green),
+            ));
+        }
     }
 
     let help_text_width: usize = help_spans.iter().map(|s| s.content.width()).sum();
     let help_bar_rows = if area.width > 0 {
-        ((help_text_width.max(1) + area.width as usize - 1) / area.width as usize) as u16
+        help_text_width.max(1).div_ceil(area.width as usize) as u16
     } else {
         1
     };
 
+    let summary = summary_lines(app);
+    let summary_rows = if summary.is_empty() {
+        0
+    } else {
+        summary.len().min(MAX_SUMMARY_ROWS) as u16 + 2
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),                   // Title + status
             Constraint::Length(app.verifier_statuses.len() as u16 + 2), // Verifier checklist
+            Constraint::Length(summary_rows),         // Failure summary (Failed/Aborted only)
             Constraint::Percentage(40),              // Logs
             Constraint::Percentage(40),              // File contents
             Constraint::Length(help_bar_rows),        // Help bar (dynamic)
@@ -393,12 +573,16 @@ fn draw_running(frame: &mut Frame, app: &App) {
         .split(area);
 
     // Title + status
+    let reason = app.end_reason.as_deref().unwrap_or_default();
     let status_text = match app.screen {
-        Screen::Done => "DONE - All verifiers passed!",
-        _ => "Working...",
+        Screen::Done => "DONE - All verifiers passed!".to_string(),
+        Screen::Failed => format!("FAILED - {}", reason),
+        Screen::Aborted => format!("ABORTED - {}", reason),
+        _ => "Working...".to_string(),
     };
     let status_color = match app.screen {
         Screen::Done => Color::Green,
+        Screen::Failed | Screen::Aborted => Color::Red,
         _ => Color::Yellow,
     };
     let title = Paragraph::new(Line::from(vec![
@@ -408,7 +592,13 @@ fn draw_running(frame: &mut Frame, app: &App) {
         ),
         Span::raw("  "),
         Span::styled(
-            format!("Status: {}  Iteration: {}", status_text, app.iteration),
+            format!(
+                "Status: {}  Iteration: {} / {}  Dir: {}",
+                status_text,
+                app.iteration,
+                app.suite_options().max_iterations_label(),
+                app.run_options.working_dir.display()
+            ),
             Style::default().fg(status_color),
         ),
     ]))
@@ -425,12 +615,16 @@ fn draw_running(frame: &mut Frame, app: &App) {
                 VerifierStatus::Running => (">>", Color::Yellow),
                 VerifierStatus::Passed => ("[x]", Color::Green),
                 VerifierStatus::Failed => ("[ ]", Color::Red),
+                VerifierStatus::TimedOut => ("[!]", Color::Magenta),
+                VerifierStatus::Skipped => ("[-]", Color::DarkGray),
             };
             let status_label = match status {
                 VerifierStatus::Pending => "pending",
                 VerifierStatus::Running => "running...",
                 VerifierStatus::Passed => "passed",
                 VerifierStatus::Failed => "FAILED",
+                VerifierStatus::TimedOut => "TIMED OUT",
+                VerifierStatus::Skipped => "skipped",
             };
             ListItem::new(Line::from(vec![
                 Span::styled(
@@ -449,13 +643,36 @@ fn draw_running(frame: &mut Frame, app: &App) {
             ]))
         })
         .collect();
+    let running = app
+        .verifier_statuses
+        .iter()
+        .filter(|(_, status)| *status == VerifierStatus::Running)
+        .count();
+    let verifier_title = if running > 0 {
+        format!(" Verifiers ({} running) ", running)
+    } else {
+        " Verifiers ".to_string()
+    };
     let verifier_list = List::new(verifier_items).block(
         Block::default()
-            .title(" Verifiers ")
+            .title(verifier_title)
             .borders(Borders::ALL),
     );
     frame.render_widget(verifier_list, chunks[1]);
 
+    // Failure summary
+    if !summary.is_empty() {
+        let summary_para = Paragraph::new(summary)
+            .block(
+                Block::default()
+                    .title(" Still failing ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(summary_para, chunks[2]);
+    }
+
     // Logs
     let log_border_color = if app.scroll_focus == ScrollFocus::Log {
         Color::Yellow
@@ -467,7 +684,7 @@ fn draw_running(frame: &mut Frame, app: &App) {
         .iter()
         .map(|l| ListItem::new(format!(" > {}", l)))
         .collect();
-    let visible_log_height = chunks[2].height.saturating_sub(2) as usize;
+    let visible_log_height = chunks[3].height.saturating_sub(2) as usize;
     let log_offset = if app.logs.len() > visible_log_height {
         (app.log_scroll as usize).min(app.logs.len().saturating_sub(visible_log_height))
     } else {
@@ -484,7 +701,7 @@ fn draw_running(frame: &mut Frame, app: &App) {
             .borders(Borders::ALL)
             .border_style(Style::default().fg(log_border_color)),
     );
-    frame.render_widget(log_list, chunks[2]);
+    frame.render_widget(log_list, chunks[3]);
 
     // File contents
     let file_border_color = if app.scroll_focus == ScrollFocus::File {
@@ -492,25 +709,271 @@ fn draw_running(frame: &mut Frame, app: &App) {
     } else {
         Color::White
     };
-    let file_para = Paragraph::new(app.file_contents.as_str())
+    let (file_lines, file_title) = if app.show_diff {
+        snapshot_diff(app)
+    } else {
+        (
+            app.file_contents.lines().map(|l| Line::raw(l.to_string())).collect(),
+            format!(
+                " File: {} ",
+                app.file_manager
+                    .as_ref()
+                    .map(|fm| fm.path.display().to_string())
+                    .unwrap_or_default()
+            ),
+        )
+    };
+    let file_para = Paragraph::new(file_lines)
         .block(
             Block::default()
-                .title(format!(
-                    " File: {} ",
-                    app.file_manager
-                        .as_ref()
-                        .map(|fm| fm.path.display().to_string())
-                        .unwrap_or_default()
-                ))
+                .title(file_title)
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(file_border_color)),
         )
         .wrap(Wrap { trim: false })
         .scroll((app.file_scroll, 0));
-    frame.render_widget(file_para, chunks[3]);
+    frame.render_widget(file_para, chunks[4]);
 
     // Render help bar
     let help = Line::from(help_spans);
     let help_bar = Paragraph::new(help).wrap(Wrap { trim: false });
-    frame.render_widget(help_bar, chunks[4]);
+    frame.render_widget(help_bar, chunks[5]);
+}
+
+/// The colored diff between the two snapshots picked on the running screen, and the
+/// panel title naming them.
+fn snapshot_diff(app: &App) -> (Vec<Line<'static>>, String) {
+    let Some((from, to)) = app.diff_pair() else {
+        return (vec![Line::raw("No snapshots yet")], " Diff ".to_string());
+    };
+    let (old, new) = (&app.snapshots[from], &app.snapshots[to]);
+    let title = format!(
+        " Diff: {} -> {} ({}/{}) ",
+        old.title(),
+        new.title(),
+        to + 1,
+        app.snapshots.len()
+    );
+    let lines = app.diff_lines();
+    if lines.is_empty() || lines.iter().all(|line| matches!(line, DiffLine::Skipped(_))) {
+        return (vec![Line::raw("No changes")], title);
+    }
+    let lines = lines
+        .iter()
+        .map(|line| match line {
+            DiffLine::Same(text) => Line::raw(format!("  {}", text)),
+            DiffLine::Added(text) => Line::styled(format!("+ {}", text), Style::default().fg(Color::Green)),
+            DiffLine::Removed(text) => Line::styled(format!("- {}", text), Style::default().fg(Color::Red)),
+            DiffLine::Skipped(n) => Line::styled(
+                format!("  ... {} unchanged line{}", n, if *n == 1 { "" } else { "s" }),
+                Style::default().fg(Color::DarkGray),
+            ),
+        })
+        .collect();
+    (lines, title)
+}
+
+/// Rows the failure summary may take before the rest is cut off.
+const MAX_SUMMARY_ROWS: usize = 10;
+
+/// Feedback lines shown per verifier in the failure summary.
+const SUMMARY_LINES_PER_VERIFIER: usize = 3;
+
+/// The failure summary for a Failed/Aborted run: each verifier whose last result was
+/// a failure, with the first few lines of its latest feedback. Empty on other screens.
+fn summary_lines(app: &App) -> Vec<Line<'static>> {
+    if !matches!(app.screen, Screen::Failed | Screen::Aborted) {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    for (i, (name, _)) in app.verifier_statuses.iter().enumerate() {
+        let status = app.last_statuses.get(i).unwrap_or(&VerifierStatus::Pending);
+        if matches!(status, VerifierStatus::Pending | VerifierStatus::Running | VerifierStatus::Passed) {
+            continue;
+        }
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(" {} ", name),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(status.label(), Style::default().fg(Color::Red)),
+        ]));
+        let feedback = app.verifier_feedback.get(i).cloned().flatten().unwrap_or_default();
+        // Section headers just repeat the verifier's name.
+        let feedback_lines = feedback
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with("=== "))
+            .take(SUMMARY_LINES_PER_VERIFIER);
+        for line in feedback_lines {
+            lines.push(Line::styled(format!("    {}", line), Style::default().fg(Color::Gray)));
+        }
+    }
+    lines
+}
+
+fn outcome_color(outcome: Outcome) -> Color {
+    match outcome {
+        Outcome::Passed => Color::Green,
+        Outcome::Failed | Outcome::Aborted => Color::Red,
+        Outcome::Running | Outcome::Interrupted => Color::Yellow,
+    }
+}
+
+fn draw_history(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Title
+            Constraint::Min(5),    // Runs + details
+            Constraint::Length(2), // Help bar
+        ])
+        .split(area);
+
+    let mut title_spans = vec![
+        Span::styled(
+            "Verifiers",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  "),
+        Span::raw(format!("History ({} runs)", app.runs.len())),
+    ];
+    if let Some(error) = &app.history_error {
+        title_spans.push(Span::raw("  "));
+        title_spans.push(Span::styled(error.clone(), Style::default().fg(Color::Red)));
+    }
+    let title = Paragraph::new(Line::from(title_spans)).block(Block::default().borders(Borders::BOTTOM));
+    frame.render_widget(title, chunks[0]);
+
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(chunks[1]);
+
+    // Run list
+    let now = history::now_ms();
+    let run_items: Vec<ListItem> = app
+        .runs
+        .iter()
+        .enumerate()
+        .map(|(i, run)| {
+            let line = Line::from(vec![
+                Span::raw(format!(" {:>8}  ", history::age_label(run.started_at_ms, now))),
+                Span::styled(
+                    format!("{:<11}", run.outcome.label()),
+                    Style::default().fg(outcome_color(run.outcome)),
+                ),
+                Span::raw(run.title().to_string()),
+            ]);
+            if i == app.selected_run {
+                ListItem::new(line).style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ListItem::new(line)
+            }
+        })
+        .collect();
+    let run_list = if run_items.is_empty() {
+        List::new(vec![ListItem::new("  No runs yet")])
+    } else {
+        List::new(run_items)
+    };
+    frame.render_widget(
+        run_list.block(Block::default().title(" Runs ").borders(Borders::ALL)),
+        panels[0],
+    );
+
+    // Details of the selected run, then its file or logs
+    let (lines, view_title) = match app.selected_run() {
+        Some(run) => {
+            let mut lines = run_details(run);
+            lines.push(Line::raw(""));
+            let body = match app.history_view {
+                ScrollFocus::File => run.final_file.lines().map(|l| Line::raw(l.to_string())).collect(),
+                ScrollFocus::Log => run.logs.iter().map(|l| Line::raw(l.clone())).collect::<Vec<_>>(),
+            };
+            lines.extend(body);
+            let view = match app.history_view {
+                ScrollFocus::File => "File",
+                ScrollFocus::Log => "Logs",
+            };
+            (lines, format!(" Run {} — {} ", run.id, view))
+        }
+        None => (Vec::new(), " Run ".to_string()),
+    };
+    let details = Paragraph::new(lines)
+        .block(Block::default().title(view_title).borders(Borders::ALL))
+        .wrap(Wrap { trim: false })
+        .scroll((app.history_scroll, 0));
+    frame.render_widget(details, panels[1]);
+
+    let help = Line::from(vec![
+        Span::styled(" Up/Down: Select ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" Tab: File/Logs ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" PgUp/PgDn: Scroll ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" Enter: Run again ", Style::default().fg(Color::Green)),
+        Span::raw(" | "),
+        Span::styled(" r: Resume ", Style::default().fg(Color::Green)),
+        Span::raw(" | "),
+        Span::styled(" Ctrl+D: Delete ", Style::default().fg(Color::Cyan)),
+        Span::raw(" | "),
+        Span::styled(" Esc: Back ", Style::default().fg(Color::Red)),
+    ]);
+    frame.render_widget(
+        Paragraph::new(help)
+            .block(Block::default().borders(Borders::TOP))
+            .wrap(Wrap { trim: false }),
+        chunks[2],
+    );
+}
+
+/// Summary lines for a past run: its setup, how it ended, and each iteration's verdicts.
+fn run_details(run: &RunRecord) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::DarkGray);
+    let duration = run
+        .finished_at_ms
+        .map(|end| history::duration_label(end.saturating_sub(run.started_at_ms)))
+        .unwrap_or_else(|| "-".to_string());
+    let mut outcome = vec![
+        Span::styled("Outcome:   ", label),
+        Span::styled(run.outcome.label().to_string(), Style::default().fg(outcome_color(run.outcome))),
+    ];
+    if let Some(reason) = &run.reason {
+        outcome.push(Span::raw(format!(" - {}", reason)));
+    }
+    let mut lines = vec![
+        Line::from(outcome),
+        Line::from(vec![
+            Span::styled("Took:      ", label),
+            Span::raw(format!("{} over {} iterations", duration, run.iterations.len())),
+        ]),
+        Line::from(vec![
+            Span::styled("Directory: ", label),
+            Span::raw(run.working_dir.display().to_string()),
+        ]),
+        Line::from(vec![Span::styled("Suite:     ", label), Span::raw(run.suite.clone())]),
+        Line::from(vec![Span::styled("Prompt:    ", label), Span::raw(run.title().to_string())]),
+    ];
+    for it in &run.iterations {
+        let mut spans = vec![Span::styled(format!("#{:<9}", it.iteration), label)];
+        for (verifier, status) in run.verifiers.iter().zip(&it.verdicts) {
+            let (mark, color) = match status {
+                VerifierStatus::Passed => ("[x]", Color::Green),
+                VerifierStatus::Failed => ("[ ]", Color::Red),
+                VerifierStatus::TimedOut => ("[!]", Color::Magenta),
+                VerifierStatus::Pending | VerifierStatus::Running | VerifierStatus::Skipped => {
+                    ("[-]", Color::DarkGray)
+                }
+            };
+            spans.push(Span::styled(format!("{} {}  ", mark, verifier.name), Style::default().fg(color)));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
 }
@@ -0,0 +1,104 @@
+use serde::Deserialize;
+
+/// A verifier's structured pass/fail decision, parsed from its stdout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verdict {
+    pub passed: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawVerdict {
+    verdict: String,
+    #[serde(default)]
+    reasons: Vec<String>,
+}
+
+/// The envelope printed by `claude --output-format json`.
+#[derive(Deserialize)]
+struct ClaudeJsonOutput {
+    result: String,
+}
+
+impl Verdict {
+    /// Render the reasons as a `=== name ===` feedback section for the shared file.
+    pub fn to_section(&self, name: &str) -> String {
+        let mut section = format!("=== {} ===\n", name);
+        if self.reasons.is_empty() {
+            section.push_str(if self.passed { "Passed.\n" } else { "Failed (no reasons given).\n" });
+        }
+        for reason in &self.reasons {
+            section.push_str(&format!("- {}\n", reason));
+        }
+        section
+    }
+}
+
+/// Find the verdict in a verifier's stdout: the last JSON object with a `verdict`
+/// field of "pass" or "fail". Returns `None` if there isn't one.
+pub fn parse_verdict(stdout: &str) -> Option<Verdict> {
+    // Unwrap `--output-format json` output to get at the agent's reply text.
+    let text = serde_json::from_str::<ClaudeJsonOutput>(stdout.trim())
+        .map(|output| output.result)
+        .unwrap_or_else(|_| stdout.to_string());
+
+    text.match_indices('{').rev().find_map(|(start, _)| {
+        let raw = serde_json::Deserializer::from_str(&text[start..])
+            .into_iter::<RawVerdict>()
+            .next()?
+            .ok()?;
+        let passed = match raw.verdict.trim().to_lowercase().as_str() {
+            "pass" | "passed" => true,
+            "fail" | "failed" => false,
+            _ => return None,
+        };
+        Some(Verdict {
+            passed,
+            reasons: raw.reasons,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_trailing_verdict_after_prose() {
+        let stdout = "I checked the output.\n```json\n{\"verdict\": \"fail\", \"reasons\": [\"no tests\"]}\n```\n";
+        assert_eq!(
+            parse_verdict(stdout),
+            Some(Verdict {
+                passed: false,
+                reasons: vec!["no tests".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn last_verdict_wins_and_nested_braces_are_skipped() {
+        let stdout = "{\"verdict\": \"fail\"}\nOn reflection {it is fine}.\n{\"verdict\": \"PASS\"}";
+        assert_eq!(parse_verdict(stdout).map(|v| v.passed), Some(true));
+    }
+
+    #[test]
+    fn unwraps_claude_json_output() {
+        let stdout = r#"{"type":"result","result":"Looks good.\n{\"verdict\":\"pass\",\"reasons\":[]}"}"#;
+        assert_eq!(parse_verdict(stdout).map(|v| v.passed), Some(true));
+    }
+
+    #[test]
+    fn missing_or_unknown_verdict_is_none() {
+        assert_eq!(parse_verdict("All good, I checked the box."), None);
+        assert_eq!(parse_verdict("{\"verdict\": \"maybe\"}"), None);
+    }
+
+    #[test]
+    fn section_lists_reasons() {
+        let verdict = Verdict {
+            passed: false,
+            reasons: vec!["a".to_string(), "b".to_string()],
+        };
+        assert_eq!(verdict.to_section("lint"), "=== lint ===\n- a\n- b\n");
+    }
+}